@@ -10,6 +10,46 @@ pub struct PrimitiveArrayAccessor<'a, T: 'a> {
     phantom: PhantomData<&'a T>,
 }
 
+// Accessors that are plain references to a single value in the data.
+pub trait ColumnAccessor {
+    type Element;
+
+    fn value_ptr(&mut self) -> *mut Self::Element;
+}
+
+impl<'a, T: 'a> ColumnAccessor for &'a mut T {
+    type Element = T;
+
+    fn value_ptr(&mut self) -> *mut T {
+        &mut **self
+    }
+}
+
+// Views one member of every element of a struct array as a single strided array. Each element has
+// its own layout, so this is only possible if the member is evenly spaced across the elements.
+pub fn column_mut<'b, E, C, F>(elements: &'b mut [E], project: F) -> Option<PrimitiveArrayAccessor<'b, C::Element>>
+        where C: ColumnAccessor + 'b, F: Fn(&mut E) -> &mut C {
+    let element_size = ::std::mem::size_of::<C::Element>();
+    let addresses: Vec<usize> = elements.iter_mut().map(|e| project(e).value_ptr() as usize).collect();
+    let stride = match addresses.len() {
+        0 => return Some(unsafe { PrimitiveArrayAccessor::new(::std::ptr::null_mut(), element_size as StrideType, 0) }),
+        1 => element_size,
+        _ if addresses[1] > addresses[0] => addresses[1] - addresses[0],
+        _ => return None,
+    };
+    if stride < element_size || stride > StrideType::max_value() as usize {
+        return None;
+    }
+    if addresses.iter().enumerate().any(|(i, &address)| address != addresses[0] + i * stride) {
+        return None;
+    }
+    Some(unsafe { PrimitiveArrayAccessor::new(addresses[0] as *mut u8, stride as StrideType, addresses.len()) })
+}
+
+// The accessor is a strided &'a mut [T], and the elements of split accessors never overlap.
+unsafe impl<'a, T: Send + 'a> Send for PrimitiveArrayAccessor<'a, T> {}
+unsafe impl<'a, T: Sync + 'a> Sync for PrimitiveArrayAccessor<'a, T> {}
+
 impl<'a, T: 'a> PrimitiveArrayAccessor<'a, T> {
     pub unsafe fn new(bytes: *mut u8, stride: StrideType, len: usize) -> PrimitiveArrayAccessor<'a, T> {
         PrimitiveArrayAccessor {
@@ -20,13 +60,89 @@ impl<'a, T: 'a> PrimitiveArrayAccessor<'a, T> {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn stride(&self) -> StrideType {
+        self.stride
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(unsafe { &*self.element_ptr(index) })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            Some(unsafe { &mut *self.element_ptr(index) })
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> Iter<T> {
+        Iter { bytes: self.bytes, stride: self.stride, front: 0, back: self.len, phantom: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<T> {
+        IterMut { bytes: self.bytes, stride: self.stride, front: 0, back: self.len, phantom: PhantomData }
+    }
+
+    pub fn copy_from_slice(&mut self, src: &[T]) where T: Copy {
+        if src.len() != self.len {
+            panic!("PrimitiveArrayAccessor::copy_from_slice: source slice length ({}) does not match accessor length ({})",
+                   src.len(),
+                   self.len);
+        }
+        for (target, value) in self.iter_mut().zip(src) {
+            *target = *value;
+        }
+    }
+
+    pub fn fill(&mut self, value: T) where T: Clone {
+        for target in self.iter_mut() {
+            *target = value.clone();
+        }
+    }
+
+    pub fn split_at_mut(&mut self, mid: usize) -> (PrimitiveArrayAccessor<T>, PrimitiveArrayAccessor<T>) {
+        if mid > self.len {
+            panic!("PrimitiveArrayAccessor::split_at_mut: mid ({}) is greater than the len ({})", mid, self.len);
+        }
+        unsafe {
+            (PrimitiveArrayAccessor::new(self.bytes, self.stride, mid),
+             PrimitiveArrayAccessor::new(self.bytes.wrapping_offset(mid as isize * self.stride as isize),
+                                         self.stride,
+                                         self.len - mid))
+        }
+    }
+
+    pub fn chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<T> {
+        if chunk_size == 0 {
+            panic!("PrimitiveArrayAccessor::chunks_mut: chunk size must be non-zero");
+        }
+        ChunksMut { bytes: self.bytes, stride: self.stride, len: self.len, chunk_size, phantom: PhantomData }
+    }
+
     fn index(&self, index: usize) -> *mut T {
         if index >= self.len {
             panic!("PrimitiveArrayAccessor index out of bounds: the len is {} but the index is {}",
                    self.len,
                    index);
         }
-        unsafe { self.bytes.offset(index as isize * self.stride as isize) as *mut T }
+        unsafe { self.element_ptr(index) }
+    }
+
+    unsafe fn element_ptr(&self, index: usize) -> *mut T {
+        self.bytes.offset(index as isize * self.stride as isize) as *mut T
     }
 }
 
@@ -43,3 +159,100 @@ impl<'a, T: 'a> IndexMut<usize> for PrimitiveArrayAccessor<'a, T> {
         unsafe { &mut *self.index(index) }
     }
 }
+
+impl<'a, 'b, T: 'a> IntoIterator for &'b PrimitiveArrayAccessor<'a, T> {
+    type Item = &'b T;
+    type IntoIter = Iter<'b, T>;
+
+    fn into_iter(self) -> Iter<'b, T> {
+        self.iter()
+    }
+}
+
+impl<'a, 'b, T: 'a> IntoIterator for &'b mut PrimitiveArrayAccessor<'a, T> {
+    type Item = &'b mut T;
+    type IntoIter = IterMut<'b, T>;
+
+    fn into_iter(self) -> IterMut<'b, T> {
+        self.iter_mut()
+    }
+}
+
+macro_rules! strided_iterator {
+    ($name:ident, $reference:ty $(, $mutability:ident)*) => (
+        pub struct $name<'a, T: 'a> {
+            bytes: *mut u8,
+            stride: StrideType,
+            front: usize,
+            back: usize,
+            phantom: PhantomData<$reference>,
+        }
+
+        impl<'a, T: 'a> Iterator for $name<'a, T> {
+            type Item = $reference;
+
+            fn next(&mut self) -> Option<$reference> {
+                if self.front == self.back {
+                    return None;
+                }
+                let ptr = unsafe { self.bytes.offset(self.front as isize * self.stride as isize) as *mut T };
+                self.front += 1;
+                Some(unsafe { &$($mutability)* *ptr })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.back - self.front;
+                (len, Some(len))
+            }
+        }
+
+        impl<'a, T: 'a> DoubleEndedIterator for $name<'a, T> {
+            fn next_back(&mut self) -> Option<$reference> {
+                if self.front == self.back {
+                    return None;
+                }
+                self.back -= 1;
+                let ptr = unsafe { self.bytes.offset(self.back as isize * self.stride as isize) as *mut T };
+                Some(unsafe { &$($mutability)* *ptr })
+            }
+        }
+
+        impl<'a, T: 'a> ExactSizeIterator for $name<'a, T> {}
+    )
+}
+
+strided_iterator!(Iter, &'a T);
+strided_iterator!(IterMut, &'a mut T, mut);
+
+pub struct ChunksMut<'a, T: 'a> {
+    bytes: *mut u8,
+    stride: StrideType,
+    len: usize,
+    chunk_size: usize,
+    phantom: PhantomData<&'a mut T>,
+}
+
+unsafe impl<'a, T: Send + 'a> Send for ChunksMut<'a, T> {}
+
+impl<'a, T: 'a> Iterator for ChunksMut<'a, T> {
+    type Item = PrimitiveArrayAccessor<'a, T>;
+
+    fn next(&mut self) -> Option<PrimitiveArrayAccessor<'a, T>> {
+        if self.len == 0 {
+            return None;
+        }
+        let len = ::std::cmp::min(self.len, self.chunk_size);
+        let chunk = unsafe { PrimitiveArrayAccessor::new(self.bytes, self.stride, len) };
+        // Stepping over the last chunk may point past the end of the data, so the offset must wrap
+        self.bytes = self.bytes.wrapping_offset(len as isize * self.stride as isize);
+        self.len -= len;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = (self.len + self.chunk_size - 1) / self.chunk_size;
+        (count, Some(count))
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for ChunksMut<'a, T> {}
@@ -0,0 +1,131 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use {Data, DynamicLayout};
+
+#[derive(Debug)]
+pub struct AllocationError {
+    pub required_data_len: usize,
+    pub available_data_len: usize,
+}
+
+pub struct Allocation<A> {
+    pub offset: usize,
+    pub accessor: A,
+}
+
+/// Packs instances of blocks into one large buffer, each at an offset rounded up to the given
+/// alignment (for example `GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT` or `minUniformBufferOffsetAlignment`).
+///
+/// The buffer is used as a ring: allocations made between two `finish_frame` calls belong to one
+/// frame, and the space of the oldest finished frame is reused once `release_frame` is called.
+///
+/// Allocations never overlap, so the accessors of one frame can all be alive at the same time.
+/// Finishing or releasing a frame needs them to be gone.
+pub struct DynamicBufferAllocator<'a> {
+    data: Data<'a>,
+    alignment: usize,
+    head: Cell<usize>,
+    tail: usize,
+    // Number of times head and tail went back to the beginning, the head is at most one lap ahead
+    head_lap: Cell<usize>,
+    tail_lap: usize,
+    frames: VecDeque<(usize, usize)>,
+}
+
+impl<'a> DynamicBufferAllocator<'a> {
+    pub fn new(data: Data<'a>, alignment: usize) -> DynamicBufferAllocator<'a> {
+        assert!(alignment.is_power_of_two(), "DynamicBufferAllocator alignment must be a power of two, got {}", alignment);
+        DynamicBufferAllocator {
+            data,
+            alignment,
+            head: Cell::new(0),
+            tail: 0,
+            head_lap: Cell::new(0),
+            tail_lap: 0,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn allocate<'b, T>(&'b self, layout: &T::Layout) -> Result<Allocation<T::Accessor>, AllocationError>
+            where T: DynamicLayout<'b> {
+        let len = T::required_data_len(layout);
+        let offset = self.reserve(len)?;
+        // The reserved range does not overlap any other allocation that can still be alive
+        let data = unsafe { self.data.sub_unchecked(offset, len) };
+        let accessor = T::make_accessor(layout, data).map_err(|e| AllocationError {
+            required_data_len: e.required_data_len,
+            available_data_len: e.data_len,
+        })?;
+        Ok(Allocation { offset, accessor })
+    }
+
+    /// Marks the end of the allocations made for the current frame.
+    pub fn finish_frame(&mut self) {
+        self.frames.push_back((self.head.get(), self.head_lap.get()));
+    }
+
+    /// Makes the space of the oldest finished frame available again. Returns false if there
+    /// were no finished frames left to release.
+    pub fn release_frame(&mut self) -> bool {
+        if let Some((end, lap)) = self.frames.pop_front() {
+            self.tail = end;
+            self.tail_lap = lap;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forgets all allocations, both the finished frames and the current one.
+    pub fn reset(&mut self) {
+        self.head.set(0);
+        self.tail = 0;
+        self.head_lap.set(0);
+        self.tail_lap = 0;
+        self.frames.clear();
+    }
+
+    fn reserve(&self, len: usize) -> Result<usize, AllocationError> {
+        let offset = align_up(self.head.get(), self.alignment);
+        if self.wrapped() {
+            if offset + len > self.tail {
+                return Err(self.allocation_error(len, offset));
+            }
+        } else if offset + len > self.capacity() {
+            // Out of room at the end, continue from the beginning if the oldest frame allows it
+            if len > self.tail {
+                return Err(self.allocation_error(len, offset));
+            }
+            self.head_lap.set(self.head_lap.get().wrapping_add(1));
+            self.head.set(len);
+            return Ok(0);
+        }
+        self.head.set(offset + len);
+        Ok(offset)
+    }
+
+    // The head is behind the tail in the buffer, and can only grow up to it
+    fn wrapped(&self) -> bool {
+        self.head_lap.get() != self.tail_lap
+    }
+
+    fn allocation_error(&self, len: usize, offset: usize) -> AllocationError {
+        let end = if self.wrapped() { self.tail } else { self.capacity() };
+        AllocationError {
+            required_data_len: len,
+            available_data_len: end.saturating_sub(offset),
+        }
+    }
+}
+
+fn align_up(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) & !(alignment - 1)
+}
@@ -0,0 +1,77 @@
+use {Data, DynamicLayout};
+
+pub const BUFFER_ALIGNMENT: usize = 16;
+
+#[repr(C, align(16))]
+#[derive(Copy, Clone)]
+struct Chunk([u8; BUFFER_ALIGNMENT]);
+
+const ZERO_CHUNK: Chunk = Chunk([0; BUFFER_ALIGNMENT]);
+
+/// Owned, zero-initialized byte buffer whose storage is aligned to `BUFFER_ALIGNMENT` bytes,
+/// so that it can be handed out as `Data` without any unsafe code.
+#[derive(Clone)]
+pub struct AlignedBuffer {
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    pub fn new() -> AlignedBuffer {
+        AlignedBuffer::with_len(0)
+    }
+
+    pub fn with_len(len: usize) -> AlignedBuffer {
+        AlignedBuffer {
+            chunks: vec![ZERO_CHUNK; chunk_count(len)],
+            len,
+        }
+    }
+
+    pub fn for_layout<'a, T: DynamicLayout<'a>>(layout: &T::Layout) -> AlignedBuffer {
+        AlignedBuffer::with_len(T::required_data_len(layout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Bytes past the old length are zeroed, also when the buffer was shrunk earlier.
+    pub fn resize(&mut self, len: usize) {
+        let old_len = self.len;
+        self.chunks.resize(chunk_count(len), ZERO_CHUNK);
+        self.len = len;
+        if len > old_len {
+            for byte in &mut self.as_mut_slice()[old_len..] {
+                *byte = 0;
+            }
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { ::std::slice::from_raw_parts(self.chunks.as_ptr() as *const u8, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { ::std::slice::from_raw_parts_mut(self.chunks.as_mut_ptr() as *mut u8, self.len) }
+    }
+
+    pub fn data(&mut self) -> Data {
+        // The storage is always aligned to BUFFER_ALIGNMENT bytes, which covers every field type
+        unsafe { Data::from_u8(self.as_mut_slice()) }
+    }
+}
+
+impl Default for AlignedBuffer {
+    fn default() -> AlignedBuffer {
+        AlignedBuffer::new()
+    }
+}
+
+fn chunk_count(len: usize) -> usize {
+    (len + BUFFER_ALIGNMENT - 1) / BUFFER_ALIGNMENT
+}
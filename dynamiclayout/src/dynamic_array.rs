@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+use {Field, TrackedField, LayoutError, OffsetType, Pod, element_extent, check_element_extents, check_shift, check_aligned};
+use load::{LayoutInfo, FieldSpan};
+use layout::DynamicArrayFieldLayout;
+use tracking::DirtyTracker;
+
+/// Marker for an array whose length is only decided when the shader is built, for example by a
+/// specialization constant. The element count comes from the layout, and the accessor is a `Vec`.
+///
+/// Accepts either `LayoutInfo::StructArrayField` with a layout per element, or
+/// `LayoutInfo::DynamicArrayField` with the layout of the first element, the stride and the count.
+pub struct DynamicArray<T>(PhantomData<T>);
+
+impl<T> Clone for DynamicArray<T> {
+    fn clone(&self) -> DynamicArray<T> {
+        DynamicArray(PhantomData)
+    }
+}
+
+impl<T> Copy for DynamicArray<T> {}
+
+impl<T> ::std::fmt::Debug for DynamicArray<T> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.write_str("DynamicArray")
+    }
+}
+
+// Zero-sized, so there are no bytes that could be padding
+unsafe impl<T: Pod> Pod for DynamicArray<T> {}
+
+// The shifts were checked when the layout was made
+fn shift_span(span: FieldSpan, shift: usize) -> FieldSpan {
+    span.shifted(shift).expect("dynamiclayout: array element span does not fit into OffsetType")
+}
+
+impl<'a, T: Field<'a>> Field<'a> for DynamicArray<T> {
+    type Layout = DynamicArrayFieldLayout<T::Layout>;
+    type Accessor = Vec<T::Accessor>;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::Layout, LayoutError> {
+        let elements = match layout_field {
+            LayoutInfo::StructArrayField(elements) => {
+                let elements = elements.iter()
+                    .map(|element| Ok((T::make_layout(LayoutInfo::StructField(*element))?, 0)))
+                    .collect::<Result<Vec<_>, LayoutError>>()?;
+                let extents = elements.iter().map(|&(ref element, _)| {
+                    let (start, len) = element_extent::<T>(element);
+                    (start as usize, len)
+                }).collect();
+                check_element_extents(extents)?;
+                elements
+            },
+            LayoutInfo::DynamicArrayField(element_info, stride, count) => {
+                // Elements, including the padding of a declared struct size, must not overlap
+                if count > 1 && element_extent::<T>(&T::make_layout(*element_info)?).1 > stride as usize {
+                    return Err(LayoutError);
+                }
+                check_aligned(T::alignment(), &[stride as usize])?;
+                (0..count)
+                    .map(|i| {
+                        let element = T::make_layout(*element_info)?;
+                        let shift = i.checked_mul(stride as usize).ok_or(LayoutError)?;
+                        check_shift::<T>(&element, shift)?;
+                        Ok((element, shift))
+                    })
+                    .collect::<Result<Vec<_>, LayoutError>>()?
+            },
+            _ => return Err(LayoutError),
+        };
+        if elements.iter().any(|&(ref element, _)| T::runtime_array_offset(element).is_some()) {
+            return Err(LayoutError);
+        }
+        Ok(DynamicArrayFieldLayout::new(elements))
+    }
+
+    unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+        layout.elements().iter()
+            .map(|&(ref element, shift)| T::make_accessor(element, data.offset(shift as isize)))
+            .collect()
+    }
+
+    fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>> {
+        let spans: Vec<_> = layout.elements().iter()
+            .flat_map(|&(ref element, shift)| T::get_field_spans(element).map(move |span| shift_span(span, shift)))
+            .collect();
+        Box::new(spans.into_iter())
+    }
+
+    fn alignment() -> usize {
+        T::alignment()
+    }
+
+    fn declared_end(layout: &Self::Layout) -> Option<usize> {
+        layout.elements().iter().filter_map(|&(ref element, shift)| T::declared_end(element).map(|end| end + shift)).max()
+    }
+
+    fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+        let spans: Vec<_> = layout.elements().iter().enumerate()
+            .flat_map(|(i, &(ref element, shift))| {
+                T::get_named_field_spans(element, &format!("{}[{}]", path, i))
+                    .map(move |(name, span)| (name, shift_span(span, shift)))
+            })
+            .collect();
+        Box::new(spans.into_iter())
+    }
+}
+
+impl<'a, T: TrackedField<'a>> TrackedField<'a> for DynamicArray<T> {
+    type TrackedAccessor = Vec<T::TrackedAccessor>;
+
+    unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        // The element shifts were checked to fit into OffsetType when the layout was made
+        layout.elements().iter()
+            .map(|&(ref element, element_shift)| {
+                T::make_tracked_accessor(element, data.offset(element_shift as isize), shift + element_shift as OffsetType, tracker)
+            })
+            .collect()
+    }
+}
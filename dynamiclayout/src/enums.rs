@@ -0,0 +1,172 @@
+use std::marker::PhantomData;
+use {Field, ArrayField, TrackedField, TrackedArrayField, ArrayHelper, LayoutError, LengthType, OffsetType, StrideType, check_aligned};
+use load::{LayoutInfo, FieldSpan};
+use layout::{SimpleFieldLayout, ArrayFieldLayout};
+use accessor::PrimitiveArrayAccessor;
+use tracking::{DirtyTracker, element_span};
+
+/// C-like enum stored as a 32-bit discriminant, usually implemented with `#[derive(LayoutEnum)]`.
+/// Signed discriminants are stored as their two's complement bits.
+pub trait LayoutEnum: Copy {
+    fn to_bits(self) -> u32;
+
+    fn from_bits(bits: u32) -> Option<Self>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidDiscriminant {
+    pub bits: u32,
+}
+
+/// Field type for enum fields, which the derive uses for fields marked with `#[dynamiclayout(discriminant)]`.
+pub struct EnumField<E>(PhantomData<E>);
+
+pub struct EnumAccessor<'a, E> {
+    value: &'a mut u32,
+    offset: OffsetType,
+    tracker: Option<&'a DirtyTracker>,
+    _phantom: PhantomData<E>,
+}
+
+impl<'a, E: LayoutEnum> EnumAccessor<'a, E> {
+    pub fn get(&self) -> Result<E, InvalidDiscriminant> {
+        E::from_bits(*self.value).ok_or(InvalidDiscriminant { bits: *self.value })
+    }
+
+    pub fn set(&mut self, value: E) {
+        *self.value = value.to_bits();
+        if let Some(tracker) = self.tracker {
+            tracker.mark(FieldSpan::new(self.offset, ::std::mem::size_of::<u32>() as LengthType));
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
+        *self.value
+    }
+}
+
+pub struct EnumArrayAccessor<'a, E> {
+    values: PrimitiveArrayAccessor<'a, u32>,
+    offset: OffsetType,
+    stride: StrideType,
+    tracker: Option<&'a DirtyTracker>,
+    _phantom: PhantomData<E>,
+}
+
+impl<'a, E: LayoutEnum> EnumArrayAccessor<'a, E> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn get(&self, index: usize) -> Result<E, InvalidDiscriminant> {
+        let bits = self.values[index];
+        E::from_bits(bits).ok_or(InvalidDiscriminant { bits })
+    }
+
+    pub fn set(&mut self, index: usize, value: E) {
+        self.values[index] = value.to_bits();
+        if let Some(tracker) = self.tracker {
+            tracker.mark(element_span(self.offset, self.stride, index, ::std::mem::size_of::<u32>()));
+        }
+    }
+}
+
+// The shift is only needed for tracking, see TrackedField
+unsafe fn enum_accessor<'a, E>(layout: &SimpleFieldLayout,
+                               data: *mut u8,
+                               shift: OffsetType,
+                               tracker: Option<&'a DirtyTracker>) -> EnumAccessor<'a, E> {
+    EnumAccessor { value: &mut *(layout.offset_ptr(data) as *mut u32), offset: layout.offset() + shift, tracker, _phantom: PhantomData }
+}
+
+unsafe fn enum_array_accessor<'a, E>(layout: &ArrayFieldLayout,
+                                     data: *mut u8,
+                                     len: usize,
+                                     shift: OffsetType,
+                                     tracker: Option<&'a DirtyTracker>) -> EnumArrayAccessor<'a, E> {
+    EnumArrayAccessor {
+        values: PrimitiveArrayAccessor::new(data.offset(layout.offset() as isize), layout.stride(), len),
+        offset: layout.offset() + shift,
+        stride: layout.stride(),
+        tracker,
+        _phantom: PhantomData
+    }
+}
+
+impl<'a, E: LayoutEnum + 'a> Field<'a> for EnumField<E> {
+    type Layout = SimpleFieldLayout;
+    type Accessor = EnumAccessor<'a, E>;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::Layout, LayoutError> {
+        if let LayoutInfo::PrimitiveField(offset) = layout_field {
+            check_aligned(::std::mem::align_of::<u32>(), &[offset as usize])?;
+            Ok(SimpleFieldLayout::new(offset))
+        } else {
+            Err(LayoutError)
+        }
+    }
+
+    unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+        enum_accessor(layout, data, 0, None)
+    }
+
+    fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>> {
+        Box::new(Some(FieldSpan::new(layout.offset(), ::std::mem::size_of::<u32>() as LengthType)).into_iter())
+    }
+
+    fn alignment() -> usize {
+        ::std::mem::align_of::<u32>()
+    }
+}
+
+impl<'a, E: LayoutEnum + 'a, L, A> ArrayField<'a, L, A> for EnumField<E>
+    where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+
+    type ArrayLayout = ArrayFieldLayout;
+    type ArrayAccessor = EnumArrayAccessor<'a, E>;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
+        if let LayoutInfo::ArrayField(offset, stride) = layout_field {
+            check_aligned(::std::mem::align_of::<u32>(), &[offset as usize, stride as usize])?;
+            Ok(ArrayFieldLayout::new(offset, stride))
+        } else {
+            Err(LayoutError)
+        }
+    }
+
+    unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
+        enum_array_accessor(layout, data, A::len(), 0, None)
+    }
+
+    fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item = FieldSpan>> {
+        let offset = layout.offset();
+        let stride = layout.stride();
+        Box::new((0..L::len() as OffsetType).map(move |i| FieldSpan::new(offset + stride * i, ::std::mem::size_of::<u32>() as LengthType)))
+    }
+}
+
+impl<'a, E: LayoutEnum + 'a> TrackedField<'a> for EnumField<E> {
+    type TrackedAccessor = EnumAccessor<'a, E>;
+
+    unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        enum_accessor(layout, data, shift, Some(tracker))
+    }
+}
+
+impl<'a, E: LayoutEnum + 'a, L, A> TrackedArrayField<'a, L, A> for EnumField<E>
+    where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+
+    type TrackedArrayAccessor = EnumArrayAccessor<'a, E>;
+
+    unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+        enum_array_accessor(layout, data, A::len(), shift, Some(tracker))
+    }
+}
@@ -0,0 +1,13 @@
+// Bit manipulation shared by the narrow float formats.
+
+// Shifts out the lowest bits of a float mantissa, rounding to nearest with ties to even.
+pub fn round_shift(value: u32, shift: u32) -> u32 {
+    let shifted = value >> shift;
+    let remainder = value & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    if remainder > halfway || (remainder == halfway && shifted & 1 == 1) {
+        shifted + 1
+    } else {
+        shifted
+    }
+}
@@ -0,0 +1,74 @@
+use float_bits::round_shift;
+
+/// IEEE 754 half-precision float (`float16_t`), stored as its bits. Arithmetic is done by
+/// converting to and from f32. Equality compares the bits, like for the other storage types.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct F16(u16);
+
+impl F16 {
+    pub fn from_bits(bits: u16) -> F16 {
+        F16(bits)
+    }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    // Rounds to the nearest half, ties to even. Values too large for a half become infinity.
+    pub fn from_f32(value: f32) -> F16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x007f_ffff;
+
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x0200 } else { 0 };
+            return F16(sign | 0x7c00 | nan);
+        }
+        let half_exponent = exponent - 127 + 15;
+        if half_exponent >= 0x1f {
+            return F16(sign | 0x7c00);
+        }
+        if half_exponent <= 0 {
+            if half_exponent < -10 {
+                return F16(sign);
+            }
+            // Subnormal, the implicit leading bit becomes part of the mantissa
+            let shift = (14 - half_exponent) as u32;
+            return F16(sign | round_shift(mantissa | 0x0080_0000, shift) as u16);
+        }
+        // A carry out of the mantissa correctly moves on to the next exponent, or infinity
+        let half = ((half_exponent as u32) << 10) + round_shift(mantissa, 13);
+        F16(sign | half as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exponent = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa = (self.0 & 0x03ff) as u32;
+        let bits = match exponent {
+            0 if mantissa == 0 => sign,
+            0 => {
+                // Subnormal halves are normal f32s
+                let shift = mantissa.leading_zeros() - 21;
+                sign | ((127 - 15 + 1 - shift) << 23) | (((mantissa << shift) & 0x03ff) << 13)
+            },
+            0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+            _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+        f32::from_bits(bits)
+    }
+}
+
+impl From<f32> for F16 {
+    fn from(value: f32) -> F16 {
+        F16::from_f32(value)
+    }
+}
+
+impl From<F16> for f32 {
+    fn from(value: F16) -> f32 {
+        value.to_f32()
+    }
+}
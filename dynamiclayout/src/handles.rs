@@ -0,0 +1,86 @@
+use std::marker::PhantomData;
+
+/// Bindless texture handle from `glGetTextureHandleARB` or `glGetTextureSamplerHandleARB`,
+/// as stored in a `sampler*` uniform with `ARB_bindless_texture`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TextureHandle(u64);
+
+impl TextureHandle {
+    pub fn from_raw(handle: u64) -> TextureHandle {
+        TextureHandle(handle)
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Bindless image handle from `glGetImageHandleARB`, as stored in an `image*` uniform.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ImageHandle(u64);
+
+impl ImageHandle {
+    pub fn from_raw(handle: u64) -> ImageHandle {
+        ImageHandle(handle)
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Buffer device address of a `T`, for `buffer_reference` blocks. The pointee type keeps
+/// pointers to different blocks from being assigned to each other.
+#[repr(C)]
+pub struct DevicePointer<T> {
+    address: u64,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> DevicePointer<T> {
+    pub fn from_address(address: u64) -> DevicePointer<T> {
+        DevicePointer { address, _phantom: PhantomData }
+    }
+
+    pub fn null() -> DevicePointer<T> {
+        DevicePointer::from_address(0)
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.address == 0
+    }
+}
+
+impl<T> Clone for DevicePointer<T> {
+    fn clone(&self) -> DevicePointer<T> {
+        DevicePointer::from_address(self.address)
+    }
+}
+
+impl<T> Copy for DevicePointer<T> {}
+
+impl<T> Default for DevicePointer<T> {
+    fn default() -> DevicePointer<T> {
+        DevicePointer::null()
+    }
+}
+
+impl<T> PartialEq for DevicePointer<T> {
+    fn eq(&self, other: &DevicePointer<T>) -> bool {
+        self.address == other.address
+    }
+}
+
+impl<T> Eq for DevicePointer<T> {}
+
+impl<T> ::std::fmt::Debug for DevicePointer<T> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "DevicePointer({:#x})", self.address)
+    }
+}
@@ -29,6 +29,12 @@ unsafe impl<'a, A, I> ArrayHelper<'a> for UnsafeArrayHelper<'a, A, I> {
         }
     }
 
+    fn array_as_mut_slice(array: &mut Self::ArrayType) -> &mut [Self::Item] {
+        unsafe {
+            opaque_array_slice_mut::<Self::ArrayType, Self::Item>(array)
+        }
+    }
+
     fn into_array(self) -> A {
         ::std::mem::ManuallyDrop::into_inner(self.0)
     }
@@ -1,5 +1,6 @@
 
-use {OffsetType, StrideType};
+use {OffsetType, StrideType, LengthType, LocationType};
+use load::MatrixOrder;
 
 #[derive(Default, Debug)]
 pub struct SimpleFieldLayout {
@@ -59,3 +60,183 @@ impl MatrixArrayFieldLayout {
         MatrixArrayFieldLayout { offset, array_stride, matrix_stride }
     }
 }
+
+#[derive(Default, Debug)]
+pub struct RuntimeArrayFieldLayout<L> {
+    element: L,
+    offset: OffsetType,
+    element_len: LengthType,
+    stride: StrideType,
+}
+
+impl<L> RuntimeArrayFieldLayout<L> {
+    pub fn new(element: L,
+               offset: OffsetType,
+               element_len: LengthType,
+               stride: StrideType) -> RuntimeArrayFieldLayout<L> {
+        RuntimeArrayFieldLayout { element, offset, element_len, stride }
+    }
+
+    pub fn element(&self) -> &L {
+        &self.element
+    }
+
+    pub fn offset(&self) -> OffsetType {
+        self.offset
+    }
+
+    pub fn stride(&self) -> StrideType {
+        self.stride
+    }
+
+    // Only complete elements are counted
+    pub fn element_count(&self, data_len: usize) -> usize {
+        let first_end = self.offset as usize + self.element_len as usize;
+        if self.stride == 0 || data_len < first_end {
+            0
+        } else {
+            (data_len - first_end) / self.stride as usize + 1
+        }
+    }
+
+    pub unsafe fn element_ptr(&self, ptr: *mut u8, index: usize) -> *mut u8 {
+        ptr.offset(self.stride as isize * index as isize)
+    }
+}
+
+// Each element is made with its own layout, from the data pointer moved by the given number of bytes.
+#[derive(Default, Debug)]
+pub struct DynamicArrayFieldLayout<L> {
+    elements: Vec<(L, usize)>,
+}
+
+impl<L> DynamicArrayFieldLayout<L> {
+    pub fn new(elements: Vec<(L, usize)>) -> DynamicArrayFieldLayout<L> {
+        DynamicArrayFieldLayout { elements }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn elements(&self) -> &[(L, usize)] {
+        &self.elements
+    }
+}
+
+// One dimension of an array of arrays: the layout of the first inner array, and the stride of
+// this dimension. Inner array i is made with the data pointer moved by i times the stride.
+#[derive(Default, Debug)]
+pub struct NestedArrayFieldLayout<L> {
+    inner: L,
+    stride: StrideType,
+    len: usize,
+}
+
+impl<L> NestedArrayFieldLayout<L> {
+    pub fn new(inner: L, stride: StrideType, len: usize) -> NestedArrayFieldLayout<L> {
+        NestedArrayFieldLayout { inner, stride, len }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    pub fn stride(&self) -> StrideType {
+        self.stride
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn shift(&self, index: usize) -> usize {
+        self.stride as usize * index
+    }
+
+    pub unsafe fn inner_ptr(&self, ptr: *mut u8, index: usize) -> *mut u8 {
+        ptr.offset(self.shift(index) as isize)
+    }
+}
+
+// Column-major matrices store the columns at matrix stride distance, row-major ones the rows.
+#[derive(Default, Debug)]
+pub struct MatrixFieldLayout {
+    offset: OffsetType,
+    matrix_stride: StrideType,
+    order: MatrixOrder,
+}
+
+impl MatrixFieldLayout {
+    pub fn new(offset: OffsetType, matrix_stride: StrideType, order: MatrixOrder) -> MatrixFieldLayout {
+        MatrixFieldLayout { offset, matrix_stride, order }
+    }
+
+    pub fn offset(&self) -> OffsetType {
+        self.offset
+    }
+
+    pub fn matrix_stride(&self) -> StrideType {
+        self.matrix_stride
+    }
+
+    pub fn order(&self) -> MatrixOrder {
+        self.order
+    }
+
+    // Distance between the elements of one column, T is the scalar type of the matrix
+    pub fn column_stride<T>(&self) -> StrideType {
+        match self.order {
+            MatrixOrder::ColumnMajor => ::std::mem::size_of::<T>() as StrideType,
+            MatrixOrder::RowMajor => self.matrix_stride,
+        }
+    }
+
+    pub fn column_offset<T>(&self, column: usize) -> OffsetType {
+        let column_offset = match self.order {
+            MatrixOrder::ColumnMajor => self.matrix_stride as usize * column,
+            MatrixOrder::RowMajor => ::std::mem::size_of::<T>() * column,
+        };
+        self.offset + column_offset as OffsetType
+    }
+
+    pub unsafe fn column_ptr<T>(&self, ptr: *mut u8, column: usize) -> *mut u8 {
+        ptr.offset(self.column_offset::<T>(column) as isize)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct LocationFieldLayout {
+    location: LocationType,
+}
+
+impl LocationFieldLayout {
+    pub fn new(location: LocationType) -> LocationFieldLayout {
+        LocationFieldLayout { location }
+    }
+
+    pub fn location(&self) -> LocationType {
+        self.location
+    }
+}
+
+// Array elements are at consecutive locations
+#[derive(Default, Debug)]
+pub struct LocationArrayFieldLayout {
+    location: LocationType,
+    len: usize,
+}
+
+impl LocationArrayFieldLayout {
+    pub fn new(location: LocationType, len: usize) -> LocationArrayFieldLayout {
+        LocationArrayFieldLayout { location, len }
+    }
+
+    pub fn location(&self) -> LocationType {
+        self.location
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
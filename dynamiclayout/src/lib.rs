@@ -6,29 +6,82 @@ pub mod vector_types;
 pub mod matrix_types;
 pub mod primitive_types;
 pub mod helper;
+pub mod allocator;
+pub mod buffer;
+pub mod tracking;
+pub mod shadow;
+pub mod runtime_array;
+pub mod dynamic_array;
+pub mod nested_array;
+pub mod half;
+pub mod handles;
+pub mod enums;
+pub mod normalized;
+pub mod vertex;
+pub mod specialization;
+pub mod push_constants;
+pub mod uniforms;
+pub mod program;
+mod float_bits;
 
 use load::{LoadStructLayout, LayoutInfo, FieldSpan};
+use tracking::DirtyTracker;
 
 pub type OffsetType = u16;
 pub type StrideType = u16;
 pub type LengthType = u16;
+// GLint, locations of default-block uniforms
+pub type LocationType = i32;
 
 #[derive(Debug)]
 pub struct LayoutError;
 
+// The data is shorter than required_data_len, or not aligned to required_alignment
 #[derive(Debug)]
 pub struct AccessorError {
     pub required_data_len: usize,
     pub data_len: usize,
+    pub required_alignment: usize,
 }
 
+#[derive(Debug)]
+pub struct AlignmentError {
+    pub address: usize,
+    pub required_alignment: usize,
+}
+
+// Alignment that Data::from_pod checks. Layouts with wider scalars, like f64 or u64, check the
+// data against their own alignment when an accessor is made.
+pub const DATA_ALIGNMENT: usize = 4;
+
+// Plain-old-data: Copy types without padding, pointers or invalid bit patterns,
+// so that any value can be viewed as bytes and any bytes as a value.
+pub unsafe trait Pod: Copy {}
+
 pub trait DynamicLayout<'a> {
     type Layout;
     type Accessor: 'a;
+    type TrackedAccessor: 'a;
 
     fn load_layout(layout_info: &LoadStructLayout) -> Result<Self::Layout, LayoutError>;
 
-    fn make_accessor(layout: &Self::Layout, data: &'a mut Data) -> Result<Self::Accessor, AccessorError>;
+    // The end of the last field. Layouts with a declared size larger than that should override this.
+    fn required_data_len(layout: &Self::Layout) -> usize {
+        Self::get_named_field_spans(layout).map(|(_, span)| span.offset as usize + span.length as usize).max().unwrap_or(0)
+    }
+
+    // The alignment of the widest scalar, make_accessor fails for data that is not aligned to it.
+    fn required_alignment(layout: &Self::Layout) -> usize;
+
+    // Takes the Data by value, so that accessors can be made for a part of a larger buffer
+    // with `Data::sub`. Use `Data::reborrow` to keep the original.
+    fn make_accessor(layout: &Self::Layout, data: Data<'a>) -> Result<Self::Accessor, AccessorError>;
+
+    fn get_named_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = (String, FieldSpan)>>;
+
+    fn make_tracked_accessor(layout: &Self::Layout,
+                             data: Data<'a>,
+                             tracker: &'a DirtyTracker) -> Result<Self::TrackedAccessor, AccessorError>;
 }
 
 pub trait Field<'a> {
@@ -39,7 +92,48 @@ pub trait Field<'a> {
 
     unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor;
 
+    // Runtime-sized arrays need to know how much data there is, everything else can ignore the length.
+    // make_accessor gives them a length of 0, so they are empty.
+    unsafe fn make_accessor_with_len(layout: &Self::Layout, data: *mut u8, _data_len: usize) -> Self::Accessor {
+        Self::make_accessor(layout, data)
+    }
+
     fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>>;
+
+    // Accessors make references to the scalars of the field, so it needs their alignment. Offsets and
+    // strides are checked against it when the layout is made, the data when the accessor is made.
+    fn alignment() -> usize;
+
+    // Only structs can declare a size larger than their spans. It is measured from the start of the
+    // struct, which is its first span when it is a field, and offset 0 when it is the whole block.
+    fn declared_len(_layout: &Self::Layout) -> Option<LengthType> {
+        None
+    }
+
+    // Where the declared size of the field ends, counted into the length of the struct containing it.
+    fn declared_end(layout: &Self::Layout) -> Option<usize> {
+        Self::declared_len(layout).map(|len| {
+            let start = Self::get_field_spans(layout).map(|span| span.offset).min().unwrap_or(0);
+            start as usize + len as usize
+        })
+    }
+
+    // Offset of the first element of a runtime-sized array at the end of the field. Such an array
+    // has to be the last thing in a block, so it can't be followed by other fields or be an array element.
+    fn runtime_array_offset(_layout: &Self::Layout) -> Option<OffsetType> {
+        None
+    }
+
+    // Fields made of several spans (like matrix columns) get an index appended to the path.
+    fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+        let spans: Vec<_> = Self::get_field_spans(layout).collect();
+        let path = path.to_string();
+        if spans.len() == 1 {
+            Box::new(spans.into_iter().map(move |span| (path.clone(), span)))
+        } else {
+            Box::new(spans.into_iter().enumerate().map(move |(i, span)| (format!("{}[{}]", path, i), span)))
+        }
+    }
 }
 
 pub trait ArrayField<'a, L, A> : Field<'a>
@@ -54,6 +148,49 @@ pub trait ArrayField<'a, L, A> : Field<'a>
     unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor;
 
     fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item = FieldSpan>>;
+
+    // Where the declared size of the last element ends, for arrays of structs
+    fn declared_end(_layout: &Self::ArrayLayout) -> Option<usize> {
+        None
+    }
+
+    fn get_named_field_spans(layout: &Self::ArrayLayout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+        let path = path.to_string();
+        Box::new(<Self as ArrayField<'a, L, A>>::get_field_spans(layout).enumerate().map(move |(i, span)| (format!("{}[{}]", path, i), span)))
+    }
+}
+
+// Accessor mode that records the byte ranges written through it into a DirtyTracker.
+//
+// Elements of runtime-sized, dynamic and nested arrays reuse the layout of the first element with the
+// data pointer moved along. `shift` is how far it was moved, and gets added to the offsets that are marked.
+pub trait TrackedField<'a> : Field<'a> {
+    type TrackedAccessor: 'a;
+
+    unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedAccessor;
+
+    unsafe fn make_tracked_accessor_with_len(layout: &Self::Layout,
+                                             data: *mut u8,
+                                             shift: OffsetType,
+                                             _data_len: usize,
+                                             tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        Self::make_tracked_accessor(layout, data, shift, tracker)
+    }
+}
+
+pub trait TrackedArrayField<'a, L, A> : ArrayField<'a, L, A> + TrackedField<'a>
+    where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+
+    type TrackedArrayAccessor: 'a;
+
+    unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor;
 }
 
 pub unsafe trait ArrayHelper<'a> {
@@ -68,6 +205,8 @@ pub unsafe trait ArrayHelper<'a> {
 
     fn array_as_slice(array: &Self::ArrayType) -> &[Self::Item];
 
+    fn array_as_mut_slice(array: &mut Self::ArrayType) -> &mut [Self::Item];
+
     fn into_array(self) -> Self::ArrayType;
 }
 
@@ -99,17 +238,102 @@ impl<'a> Data<'a> {
         }
     }
 
+    pub fn from_pod<T: Pod>(x: &mut T) -> Result<Data, AlignmentError> {
+        check_data_alignment(x as *mut T as *mut u8)?;
+        Ok(unsafe { Data::from_anything(x) })
+    }
+
+    pub fn from_pod_slice<T: Pod>(x: &mut [T]) -> Result<Data, AlignmentError> {
+        let ptr = x.as_mut_ptr() as *mut u8;
+        check_data_alignment(ptr)?;
+        Ok(Data {
+            ptr,
+            len: ::std::mem::size_of::<T>() * x.len(),
+            _phantom: ::std::marker::PhantomData
+        })
+    }
+
     pub unsafe fn unsafe_clone(&self) -> Data {
         Data {
             ptr: self.ptr, len: self.len, _phantom: ::std::marker::PhantomData
         }
     }
 
+    /// The same bytes, borrowed from this Data for a shorter lifetime.
+    pub fn reborrow(&mut self) -> Data {
+        Data {
+            ptr: self.ptr, len: self.len, _phantom: ::std::marker::PhantomData
+        }
+    }
+
+    // Unsafe because the caller has to make sure that the ranges handed out at the same time don't overlap.
+    unsafe fn sub_unchecked(&self, offset: usize, len: usize) -> Data {
+        Data {
+            ptr: self.ptr.offset(offset as isize), len, _phantom: ::std::marker::PhantomData
+        }
+    }
+
+    /// The bytes in `range`, or `None` if the range is out of bounds.
+    pub fn sub(self, range: ::std::ops::Range<usize>) -> Option<Data<'a>> {
+        if range.start > range.end || range.end > self.len {
+            return None;
+        }
+        Some(Data {
+            ptr: unsafe { self.ptr.offset(range.start as isize) },
+            len: range.end - range.start,
+            _phantom: ::std::marker::PhantomData
+        })
+    }
+
     pub fn as_ptr(&mut self) -> *mut u8 {
         self.ptr
     }
 }
 
+fn check_data_alignment(ptr: *mut u8) -> Result<(), AlignmentError> {
+    let address = ptr as usize;
+    if address % DATA_ALIGNMENT != 0 {
+        return Err(AlignmentError { address, required_alignment: DATA_ALIGNMENT });
+    }
+    Ok(())
+}
+
+// Offsets and strides of a layout must be multiples of the alignment, see Field::alignment.
+fn check_aligned(alignment: usize, values: &[usize]) -> Result<(), LayoutError> {
+    if values.iter().all(|value| value % alignment == 0) {
+        Ok(())
+    } else {
+        Err(LayoutError)
+    }
+}
+
+// Start and length of an array element, covering both its spans and the size it declares.
+fn element_extent<'a, T: Field<'a>>(layout: &T::Layout) -> (OffsetType, usize) {
+    let spans: Vec<_> = T::get_field_spans(layout).collect();
+    let start = spans.iter().map(|span| span.offset).min().unwrap_or(0);
+    let end = spans.iter().map(|span| span.offset as usize + span.length as usize).max().unwrap_or(0);
+    (start, ::std::cmp::max(end, T::declared_end(layout).unwrap_or(0)) - start as usize)
+}
+
+// Elements of struct arrays may be in any order, but including their declared size they must not overlap.
+fn check_element_extents(mut extents: Vec<(usize, usize)>) -> Result<(), LayoutError> {
+    extents.sort();
+    if extents.windows(2).any(|pair| pair[0].0 + pair[0].1 > pair[1].0) {
+        Err(LayoutError)
+    } else {
+        Ok(())
+    }
+}
+
+// Array elements are made by moving the spans of the first one, the moved spans must still fit into OffsetType.
+fn check_shift<'a, T: Field<'a>>(layout: &T::Layout, shift: usize) -> Result<(), LayoutError> {
+    if T::get_field_spans(layout).all(|span| span.shifted(shift).is_some()) {
+        Ok(())
+    } else {
+        Err(LayoutError)
+    }
+}
+
 pub fn make_array_layout<'a, T, L>(layout_field: LayoutInfo) -> Result<L::ArrayType, LayoutError>
         where T: Field<'a>, L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout> {
     if let LayoutInfo::StructArrayField(elements) = layout_field {
@@ -119,13 +343,20 @@ pub fn make_array_layout<'a, T, L>(layout_field: LayoutInfo) -> Result<L::ArrayT
             if slice.len() != elements.len() {
                 return Err(LayoutError);
             }
+            let mut extents = Vec::new();
             for i in 0..slice.len() {
                 let target = &mut slice[i];
                 let layout = <T as Field<'a>>::make_layout(LayoutInfo::StructField(elements[i]))?;
+                if T::runtime_array_offset(&layout).is_some() {
+                    return Err(LayoutError);
+                }
+                let (start, len) = element_extent::<T>(&layout);
+                extents.push((start as usize, len));
                 unsafe {
                     ::std::ptr::write(target, layout);
                 }
             }
+            check_element_extents(extents)?;
         }
         Ok(helper.into_array())
     } else {
@@ -152,6 +383,15 @@ pub unsafe fn make_array_accessor<'a, T, L, A>(layout: &L::ArrayType, data: *mut
     helper.into_array()
 }
 
+pub unsafe fn make_tracked_array_accessor<'a, T, L>(layout: &L::ArrayType,
+                                                   data: *mut u8,
+                                                   shift: OffsetType,
+                                                   tracker: &'a DirtyTracker) -> Vec<T::TrackedAccessor>
+        where T: TrackedField<'a>, L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout> {
+    let layouts = <L as ArrayHelper<'a>>::array_as_slice(layout);
+    layouts.iter().map(|l| <T as TrackedField<'a>>::make_tracked_accessor(l, data, shift, tracker)).collect()
+}
+
 pub fn get_array_field_spans<'a, T, L>(layout: &L::ArrayType) -> Box<Iterator<Item = FieldSpan>>
         where T: Field<'a>, L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout> {
     let layouts = <L as ArrayHelper<'a>>::array_as_slice(layout);
@@ -159,6 +399,37 @@ pub fn get_array_field_spans<'a, T, L>(layout: &L::ArrayType) -> Box<Iterator<It
     Box::new(spans.into_iter())
 }
 
+pub fn get_array_named_field_spans<'a, T, L>(layout: &L::ArrayType, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>>
+        where T: Field<'a>, L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout> {
+    let layouts = <L as ArrayHelper<'a>>::array_as_slice(layout);
+    let spans: Vec<_> = layouts.iter().enumerate()
+        .flat_map(|(i, l)| <T as Field<'a>>::get_named_field_spans(l, &format!("{}[{}]", path, i)))
+        .collect();
+    Box::new(spans.into_iter())
+}
+
+pub fn get_array_declared_end<'a, T, L>(layout: &L::ArrayType) -> Option<usize>
+        where T: Field<'a>, L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout> {
+    // Elements of struct arrays can be in any order, so the last one is not always the highest
+    <L as ArrayHelper<'a>>::array_as_slice(layout).iter().filter_map(|l| <T as Field<'a>>::declared_end(l)).max()
+}
+
+// Accessors can only be made for data that is long enough, and aligned for the widest scalar
+pub fn check_data(data: &mut Data, required_data_len: usize, required_alignment: usize) -> Result<(), AccessorError> {
+    if data.len() < required_data_len || data.as_ptr() as usize % required_alignment != 0 {
+        return Err(AccessorError { required_data_len, data_len: data.len(), required_alignment });
+    }
+    Ok(())
+}
+
+pub fn join_field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
 pub fn validate_field_spans(mut spans: Vec<FieldSpan>) -> Result<(), LayoutError> {
     // Are 0 spans okay? I guess there won't be any overlap in that case, so let's consider that okay
     if spans.len() < 2 {
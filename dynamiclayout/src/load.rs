@@ -1,18 +1,50 @@
 
-use super::{OffsetType, StrideType, LengthType};
+use super::{OffsetType, StrideType, LengthType, LocationType};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MatrixOrder {
+    ColumnMajor,
+    RowMajor,
+}
+
+impl Default for MatrixOrder {
+    fn default() -> MatrixOrder {
+        MatrixOrder::ColumnMajor
+    }
+}
 
 #[derive(Copy, Clone)]
 pub enum LayoutInfo<'a> {
     PrimitiveField(OffsetType),
+    // Also accepted for matrices, which are then column-major with the stride as matrix stride
     ArrayField(OffsetType, StrideType),
+    // Offset, matrix stride and storage order
+    MatrixField(OffsetType, StrideType, MatrixOrder),
+    // Offset, array stride and matrix stride of column-major matrices
     MatrixArrayField(OffsetType, StrideType, StrideType),
+    // Like MatrixArrayField, with the storage order
+    OrderedMatrixArrayField(OffsetType, StrideType, StrideType, MatrixOrder),
     StructField(&'a LoadStructLayout),
     StructArrayField(&'a [&'a LoadStructLayout]),
+    // Layout of the first element and the array stride, the element count depends on the data length
+    RuntimeArrayField(&'a LayoutInfo<'a>, StrideType),
+    // Layout of the first element, the array stride and the element count
+    DynamicArrayField(&'a LayoutInfo<'a>, StrideType, usize),
+    // Layout of the first inner array and the stride of the outer dimension, for arrays of arrays
+    NestedArrayField(&'a LayoutInfo<'a>, StrideType),
+    // Location of a default-block uniform, for arrays the location of the first element
+    LocationField(LocationType),
 }
 
 
 pub trait LoadStructLayout {
     fn get_field_layout(&self, field_name: &str) -> Option<LayoutInfo>;
+
+    // Size the shader declares for the struct or block, including trailing padding that is not
+    // part of any field (GL_UNIFORM_BLOCK_DATA_SIZE, or a std140 struct rounded up to 16 bytes).
+    fn declared_size(&self) -> Option<usize> {
+        None
+    }
 }
 
 impl<'a> LoadStructLayout for LayoutInfo<'a> {
@@ -22,6 +54,29 @@ impl<'a> LoadStructLayout for LayoutInfo<'a> {
             _ => None,
         }
     }
+
+    fn declared_size(&self) -> Option<usize> {
+        match *self {
+            LayoutInfo::StructField(ref inner) => inner.declared_size(),
+            _ => None,
+        }
+    }
+}
+
+/// Adds the declared size to the field layouts of a struct or block.
+pub struct SizedStructLayout<'a> {
+    pub fields: &'a LoadStructLayout,
+    pub size: usize,
+}
+
+impl<'a> LoadStructLayout for SizedStructLayout<'a> {
+    fn get_field_layout(&self, field_name: &str) -> Option<LayoutInfo> {
+        self.fields.get_field_layout(field_name)
+    }
+
+    fn declared_size(&self) -> Option<usize> {
+        Some(self.size)
+    }
 }
 
 impl<'a, F: AsRef<str>> LoadStructLayout for &'a [(F, LayoutInfo<'a>)] {
@@ -30,7 +85,7 @@ impl<'a, F: AsRef<str>> LoadStructLayout for &'a [(F, LayoutInfo<'a>)] {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct FieldSpan {
     pub offset: OffsetType,
     pub length: LengthType,
@@ -43,4 +98,14 @@ impl FieldSpan {
             length
         }
     }
+
+    /// The span moved by `shift` bytes, `None` if it would no longer fit into `OffsetType`.
+    pub fn shifted(self, shift: usize) -> Option<FieldSpan> {
+        if shift > OffsetType::max_value() as usize {
+            return None;
+        }
+        let offset = self.offset.checked_add(shift as OffsetType)?;
+        offset.checked_add(self.length)?;
+        Some(FieldSpan::new(offset, self.length))
+    }
 }
@@ -1,8 +1,10 @@
 
 use std::ops::{Index, IndexMut};
-use {OffsetType, LengthType, StrideType, Field, ArrayField, ArrayHelper, LayoutError};
-use layout::ArrayFieldLayout;
-use load::{FieldSpan, LayoutInfo};
+use {OffsetType, LengthType, StrideType, Field, ArrayField, TrackedField, TrackedArrayField, ArrayHelper, LayoutError, Pod, check_aligned};
+use tracking::{DirtyTracker, TrackedValue, TrackedArrayAccessor};
+use accessor::PrimitiveArrayAccessor;
+use layout::MatrixFieldLayout;
+use load::{FieldSpan, LayoutInfo, MatrixOrder};
 //use {LayoutInfo, ArrayFieldLayout, MatrixArrayFieldLayout, LayoutDynamicField, AccessDynamicField,
 //     FieldSpan, OffsetType, LengthType, LayoutArrayDynamicField, AccessArrayDynamicField};
 
@@ -11,30 +13,154 @@ pub struct MatrixArrayFieldLayout {
     offset: OffsetType,
     array_stride: StrideType,
     matrix_stride: StrideType,
+    order: MatrixOrder,
+}
+
+impl MatrixArrayFieldLayout {
+    // Layout of the matrix at index, with the offset relative to the start of the block
+    fn element(&self, index: usize) -> MatrixFieldLayout {
+        MatrixFieldLayout::new(self.offset + self.array_stride * index as OffsetType, self.matrix_stride, self.order)
+    }
+}
+
+/// Field type for a matrix stored row-major (GLSL `row_major`). It is loaded from `LayoutInfo::MatrixField`
+/// or `OrderedMatrixArrayField` with `MatrixOrder::RowMajor`, and the accessor is still indexed as
+/// `m[column][row]`, with every column being a strided array.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+pub struct RowMajor<M: Copy>(pub M);
+
+unsafe impl<M: Pod> Pod for RowMajor<M> {}
+
+// The plain matrix types only accept column-major layouts, RowMajor only row-major ones.
+// T is the scalar type, which the columns are made of.
+fn make_matrix_layout<T>(layout_field: LayoutInfo, order: MatrixOrder) -> Result<MatrixFieldLayout, LayoutError> {
+    let (offset, matrix_stride) = match layout_field {
+        LayoutInfo::ArrayField(offset, stride) if order == MatrixOrder::ColumnMajor => (offset, stride),
+        LayoutInfo::MatrixField(offset, matrix_stride, field_order) if field_order == order => (offset, matrix_stride),
+        _ => return Err(LayoutError)
+    };
+    check_aligned(::std::mem::align_of::<T>(), &[offset as usize, matrix_stride as usize])?;
+    Ok(MatrixFieldLayout::new(offset, matrix_stride, order))
+}
+
+fn make_matrix_array_layout<T>(layout_field: LayoutInfo, order: MatrixOrder) -> Result<MatrixArrayFieldLayout, LayoutError> {
+    let (offset, array_stride, matrix_stride) = match layout_field {
+        LayoutInfo::MatrixArrayField(offset, array_stride, matrix_stride) if order == MatrixOrder::ColumnMajor => {
+            (offset, array_stride, matrix_stride)
+        },
+        LayoutInfo::OrderedMatrixArrayField(offset, array_stride, matrix_stride, field_order) if field_order == order => {
+            (offset, array_stride, matrix_stride)
+        },
+        _ => return Err(LayoutError)
+    };
+    check_aligned(::std::mem::align_of::<T>(), &[offset as usize, array_stride as usize, matrix_stride as usize])?;
+    Ok(MatrixArrayFieldLayout { offset, array_stride, matrix_stride, order })
+}
+
+// Column-major matrices are stored as column vectors, row-major ones as row vectors
+fn matrix_spans<T>(layout: &MatrixFieldLayout, shift: OffsetType, column_count: usize, row_count: usize) -> Vec<FieldSpan> {
+    let (vector_count, vector_len) = match layout.order() {
+        MatrixOrder::ColumnMajor => (column_count, row_count),
+        MatrixOrder::RowMajor => (row_count, column_count),
+    };
+    (0..vector_count).map(|i| FieldSpan {
+        offset: layout.offset() + shift + layout.matrix_stride() * i as OffsetType,
+        length: (::std::mem::size_of::<T>() * vector_len) as LengthType,
+    }).collect()
+}
+
+// A row of a row-major matrix holds one element of every column, so those are named by element.
+fn named_matrix_spans<T>(layout: &MatrixFieldLayout,
+                      shift: OffsetType,
+                      path: &str,
+                      column_count: usize,
+                      row_count: usize) -> Vec<(String, FieldSpan)> {
+    match layout.order() {
+        MatrixOrder::ColumnMajor => {
+            matrix_spans::<T>(layout, shift, column_count, row_count).into_iter().enumerate()
+                .map(|(c, span)| (format!("{}[{}]", path, c), span))
+                .collect()
+        },
+        MatrixOrder::RowMajor => {
+            let element_len = ::std::mem::size_of::<T>() as LengthType;
+            (0..column_count).flat_map(|c| (0..row_count).map(move |r| (c, r)))
+                .map(|(c, r)| {
+                    let offset = layout.offset() + shift + layout.matrix_stride() * r as OffsetType + element_len * c as OffsetType;
+                    (format!("{}[{}][{}]", path, c, r), FieldSpan::new(offset, element_len))
+                })
+                .collect()
+        },
+    }
+}
+
+fn matrix_array_spans<T>(layout: &MatrixArrayFieldLayout, len: usize, column_count: usize, row_count: usize) -> Vec<FieldSpan> {
+    (0..len).flat_map(|i| matrix_spans::<T>(&layout.element(i), 0, column_count, row_count)).collect()
+}
+
+fn named_matrix_array_spans<T>(layout: &MatrixArrayFieldLayout,
+                               len: usize,
+                               path: &str,
+                               column_count: usize,
+                               row_count: usize) -> Vec<(String, FieldSpan)> {
+    (0..len)
+        .flat_map(|i| named_matrix_spans::<T>(&layout.element(i), 0, &format!("{}[{}]", path, i), column_count, row_count))
+        .collect()
 }
 
 macro_rules! make_matrix_type {
-    ($matrix_type:ident [$column_count:expr][$row_count:expr] $($field:expr),+) => (
+    ($matrix_type:ident : $scalar_type:ty [$column_count:expr][$row_count:expr] $($field:expr),+) => (
         #[repr(C, packed)]
         #[derive(Debug, Copy, Clone)]
-        pub struct $matrix_type ([[f32; $row_count]; $column_count]);
+        pub struct $matrix_type ([[$scalar_type; $row_count]; $column_count]);
 
         impl $matrix_type {
-            pub fn new(data: [[f32; $row_count]; $column_count]) -> $matrix_type {
+            pub fn new(data: [[$scalar_type; $row_count]; $column_count]) -> $matrix_type {
                 $matrix_type(data)
             }
 
-            // TODO: Make sure this actually does what it should
-            #[allow(dead_code)]
             unsafe fn accessor_from_layout<'a, 'b>(layout: &'a <Self as Field>::Layout, bytes: *mut u8) -> <Self as Field<'b>>::Accessor {
                 [
-                    $( &mut *(layout.offset_ptr(bytes, $field) as *mut [f32; $row_count]) ),+
+                    $( &mut *(layout.column_ptr::<$scalar_type>(bytes, $field) as *mut [$scalar_type; $row_count]) ),+
+                ]
+            }
+
+            // The layout offset must be relative to the start of the block, as it is also what gets marked as written
+            unsafe fn tracked_accessor_from_layout<'a>(layout: &MatrixFieldLayout,
+                                                       bytes: *mut u8,
+                                                       shift: OffsetType,
+                                                       tracker: &'a DirtyTracker) -> <Self as TrackedField<'a>>::TrackedAccessor {
+                [
+                    $( TrackedValue::new(&mut *(layout.column_ptr::<$scalar_type>(bytes, $field) as *mut [$scalar_type; $row_count]),
+                                         layout.column_offset::<$scalar_type>($field) + shift,
+                                         tracker) ),+
+                ]
+            }
+
+            // Every column of a row-major matrix is a strided array
+            unsafe fn row_major_accessor_from_layout<'b>(layout: &MatrixFieldLayout, bytes: *mut u8) -> <RowMajor<Self> as Field<'b>>::Accessor {
+                [
+                    $( PrimitiveArrayAccessor::new(layout.column_ptr::<$scalar_type>(bytes, $field), layout.column_stride::<$scalar_type>(), $row_count) ),+
+                ]
+            }
+
+            unsafe fn row_major_tracked_accessor_from_layout<'a>(layout: &MatrixFieldLayout,
+                                                                 bytes: *mut u8,
+                                                                 shift: OffsetType,
+                                                                 tracker: &'a DirtyTracker) -> <RowMajor<Self> as TrackedField<'a>>::TrackedAccessor {
+                [
+                    $( TrackedArrayAccessor::new(PrimitiveArrayAccessor::new(layout.column_ptr::<$scalar_type>(bytes, $field), layout.column_stride::<$scalar_type>(), $row_count),
+                                                 layout.column_offset::<$scalar_type>($field) + shift,
+                                                 layout.column_stride::<$scalar_type>(),
+                                                 tracker) ),+
                 ]
             }
         }
 
+        unsafe impl Pod for $matrix_type {}
+
         impl Index<usize> for $matrix_type {
-            type Output = [f32; $row_count];
+            type Output = [$scalar_type; $row_count];
 
             fn index(&self, index: usize) -> &Self::Output {
                 &self.0[index]
@@ -48,29 +174,28 @@ macro_rules! make_matrix_type {
         }
 
         impl<'a> Field<'a> for $matrix_type {
-            type Layout = ArrayFieldLayout;
-            type Accessor = [&'a mut [f32; $row_count]; $column_count];
+            type Layout = MatrixFieldLayout;
+            type Accessor = [&'a mut [$scalar_type; $row_count]; $column_count];
 
             fn make_layout(layout_field: ::LayoutInfo) -> Result<Self::Layout, LayoutError> {
-                if let LayoutInfo::ArrayField (offset, stride) = layout_field {
-                    Ok(ArrayFieldLayout::new(offset, stride))
-                } else {
-                    Err(LayoutError)
-                }
+                make_matrix_layout::<$scalar_type>(layout_field, MatrixOrder::ColumnMajor)
             }
 
             fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item=FieldSpan>> {
-                let offset = layout.offset();
-                let stride = layout.stride();
-                Box::new((0..$column_count).map(move |i| FieldSpan {
-                    offset: (offset + stride * i) as OffsetType,
-                    length: (::std::mem::size_of::<f32>() * $row_count) as LengthType,
-                }))
+                Box::new(matrix_spans::<$scalar_type>(layout, 0, $column_count, $row_count).into_iter())
+            }
+
+            fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item=(String, FieldSpan)>> {
+                Box::new(named_matrix_spans::<$scalar_type>(layout, 0, path, $column_count, $row_count).into_iter())
             }
 
             unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
                 $matrix_type::accessor_from_layout(layout, data)
             }
+
+            fn alignment() -> usize {
+                ::std::mem::align_of::<$scalar_type>()
+            }
         }
 
 
@@ -82,33 +207,23 @@ macro_rules! make_matrix_type {
             type ArrayAccessor = A::ArrayType;
 
             fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
-                if let LayoutInfo::MatrixArrayField(offset, array_stride, matrix_stride) = layout_field {
-                    Ok(MatrixArrayFieldLayout { offset: offset, array_stride: array_stride, matrix_stride: matrix_stride })
-                } else {
-                    Err(LayoutError)
-                }
+                make_matrix_array_layout::<$scalar_type>(layout_field, MatrixOrder::ColumnMajor)
             }
 
             fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item=FieldSpan>> {
-                let offset = layout.offset;
-                let array_stride = layout.array_stride;
-                let matrix_stride = layout.matrix_stride;
-                Box::new((0..L::len() as u16).flat_map(move |i| (0..$column_count).map(move |r| FieldSpan {
-                    offset: (offset + array_stride * i + matrix_stride * r) as OffsetType,
-                    length: ::std::mem::size_of::<f32>() as LengthType * $row_count as LengthType,
-                })))
+                Box::new(matrix_array_spans::<$scalar_type>(layout, L::len(), $column_count, $row_count).into_iter())
+            }
+
+            fn get_named_field_spans(layout: &Self::ArrayLayout, path: &str) -> Box<Iterator<Item=(String, FieldSpan)>> {
+                Box::new(named_matrix_array_spans::<$scalar_type>(layout, L::len(), path, $column_count, $row_count).into_iter())
             }
 
             unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
                 let mut ah = A::uninitialized();
                 {
                     let slice = ah.as_mut_slice();
-                    // The pointer given to accessor_from_layout already has the offset calculated, therefore use 0 here
-                    // TODO: Is it really?
-                    let matrix_layout = ArrayFieldLayout::new(0, layout.matrix_stride);
                     for i in 0..A::len() {
-                        let offset = (i as OffsetType) * layout.array_stride + layout.offset;
-                        let accessor = $matrix_type::accessor_from_layout(&matrix_layout, data.offset(offset as isize));
+                        let accessor = $matrix_type::accessor_from_layout(&layout.element(i), data);
                         let target: *mut A::Item = &mut slice[i];
                         // Use ptr::write to avoid calling drop on the (uninitialized) target memory
                         ::std::ptr::write(target, accessor);
@@ -117,15 +232,130 @@ macro_rules! make_matrix_type {
                 ah.into_array()
             }
         }
+
+        impl<'a> TrackedField<'a> for $matrix_type {
+            type TrackedAccessor = [TrackedValue<'a, [$scalar_type; $row_count]>; $column_count];
+
+            unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+                $matrix_type::tracked_accessor_from_layout(layout, data, shift, tracker)
+            }
+        }
+
+        impl<'a, L, A> TrackedArrayField<'a, L, A> for $matrix_type
+            where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+                A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+            type TrackedArrayAccessor = Vec<<$matrix_type as TrackedField<'a>>::TrackedAccessor>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+                (0..A::len()).map(|i| $matrix_type::tracked_accessor_from_layout(&layout.element(i), data, shift, tracker)).collect()
+            }
+        }
+
+        impl<'a> Field<'a> for RowMajor<$matrix_type> {
+            type Layout = MatrixFieldLayout;
+            type Accessor = [PrimitiveArrayAccessor<'a, $scalar_type>; $column_count];
+
+            fn make_layout(layout_field: ::LayoutInfo) -> Result<Self::Layout, LayoutError> {
+                make_matrix_layout::<$scalar_type>(layout_field, MatrixOrder::RowMajor)
+            }
+
+            fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item=FieldSpan>> {
+                Box::new(matrix_spans::<$scalar_type>(layout, 0, $column_count, $row_count).into_iter())
+            }
+
+            fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item=(String, FieldSpan)>> {
+                Box::new(named_matrix_spans::<$scalar_type>(layout, 0, path, $column_count, $row_count).into_iter())
+            }
+
+            unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+                $matrix_type::row_major_accessor_from_layout(layout, data)
+            }
+
+            fn alignment() -> usize {
+                ::std::mem::align_of::<$scalar_type>()
+            }
+        }
+
+        impl<'a, L, A> ArrayField<'a, L, A> for RowMajor<$matrix_type>
+            where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+                A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+            type ArrayLayout = MatrixArrayFieldLayout;
+            type ArrayAccessor = A::ArrayType;
+
+            fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
+                make_matrix_array_layout::<$scalar_type>(layout_field, MatrixOrder::RowMajor)
+            }
+
+            fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item=FieldSpan>> {
+                Box::new(matrix_array_spans::<$scalar_type>(layout, L::len(), $column_count, $row_count).into_iter())
+            }
+
+            fn get_named_field_spans(layout: &Self::ArrayLayout, path: &str) -> Box<Iterator<Item=(String, FieldSpan)>> {
+                Box::new(named_matrix_array_spans::<$scalar_type>(layout, L::len(), path, $column_count, $row_count).into_iter())
+            }
+
+            unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
+                let mut ah = A::uninitialized();
+                {
+                    let slice = ah.as_mut_slice();
+                    for i in 0..A::len() {
+                        let accessor = $matrix_type::row_major_accessor_from_layout(&layout.element(i), data);
+                        let target: *mut A::Item = &mut slice[i];
+                        ::std::ptr::write(target, accessor);
+                    }
+                }
+                ah.into_array()
+            }
+        }
+
+        impl<'a> TrackedField<'a> for RowMajor<$matrix_type> {
+            type TrackedAccessor = [TrackedArrayAccessor<'a, $scalar_type>; $column_count];
+
+            unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+                $matrix_type::row_major_tracked_accessor_from_layout(layout, data, shift, tracker)
+            }
+        }
+
+        impl<'a, L, A> TrackedArrayField<'a, L, A> for RowMajor<$matrix_type>
+            where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+                A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+            type TrackedArrayAccessor = Vec<<RowMajor<$matrix_type> as TrackedField<'a>>::TrackedAccessor>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+                (0..A::len()).map(|i| $matrix_type::row_major_tracked_accessor_from_layout(&layout.element(i), data, shift, tracker)).collect()
+            }
+        }
     );
 }
 
-make_matrix_type!(Matrix2 [2][2] 0, 1);
-make_matrix_type!(Matrix2x3 [2][3] 0, 1);
-make_matrix_type!(Matrix2x4 [2][4] 0, 1);
-make_matrix_type!(Matrix3x2 [3][2] 0, 1, 2);
-make_matrix_type!(Matrix3 [3][3] 0, 1, 2);
-make_matrix_type!(Matrix3x4 [3][4] 0, 1, 2);
-make_matrix_type!(Matrix4x2 [4][2] 0, 1, 2, 3);
-make_matrix_type!(Matrix4x3 [4][3] 0, 1, 2, 3);
-make_matrix_type!(Matrix4 [4][4] 0, 1, 2, 3);
+make_matrix_type!(Matrix2: f32 [2][2] 0, 1);
+make_matrix_type!(Matrix2x3: f32 [2][3] 0, 1);
+make_matrix_type!(Matrix2x4: f32 [2][4] 0, 1);
+make_matrix_type!(Matrix3x2: f32 [3][2] 0, 1, 2);
+make_matrix_type!(Matrix3: f32 [3][3] 0, 1, 2);
+make_matrix_type!(Matrix3x4: f32 [3][4] 0, 1, 2);
+make_matrix_type!(Matrix4x2: f32 [4][2] 0, 1, 2, 3);
+make_matrix_type!(Matrix4x3: f32 [4][3] 0, 1, 2, 3);
+make_matrix_type!(Matrix4: f32 [4][4] 0, 1, 2, 3);
+
+make_matrix_type!(DMatrix2: f64 [2][2] 0, 1);
+make_matrix_type!(DMatrix2x3: f64 [2][3] 0, 1);
+make_matrix_type!(DMatrix2x4: f64 [2][4] 0, 1);
+make_matrix_type!(DMatrix3x2: f64 [3][2] 0, 1, 2);
+make_matrix_type!(DMatrix3: f64 [3][3] 0, 1, 2);
+make_matrix_type!(DMatrix3x4: f64 [3][4] 0, 1, 2);
+make_matrix_type!(DMatrix4x2: f64 [4][2] 0, 1, 2, 3);
+make_matrix_type!(DMatrix4x3: f64 [4][3] 0, 1, 2, 3);
+make_matrix_type!(DMatrix4: f64 [4][4] 0, 1, 2, 3);
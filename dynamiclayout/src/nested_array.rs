@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+use {Field, ArrayField, TrackedField, TrackedArrayField, ArrayHelper, LayoutError, OffsetType, element_extent, check_shift, check_aligned};
+use load::{LayoutInfo, FieldSpan};
+use layout::NestedArrayFieldLayout;
+use tracking::DirtyTracker;
+
+/// Makes an array of `T` usable as the element of another array, for arrays of arrays
+/// (`float weights[8][4];`). `L` and `A` are the array helpers of the inner dimension.
+///
+/// The derive wraps every dimension but the outermost one in this type, so `[[f32; 4]; 8]`
+/// becomes an array of 8 `ArrayOf<f32, _, _>`. Each outer dimension is described by
+/// `LayoutInfo::NestedArrayField` with the layout of the first inner array and its own stride.
+pub struct ArrayOf<T, L, A>(PhantomData<(T, L, A)>);
+
+// The shift of the last inner array was checked when the layout was made
+fn shift_span(span: FieldSpan, shift: usize) -> FieldSpan {
+    span.shifted(shift).expect("dynamiclayout: inner array span does not fit into OffsetType")
+}
+
+impl<'a, T, L, A> Field<'a> for ArrayOf<T, L, A>
+    where T: ArrayField<'a, L, A>,
+        L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<T as Field<'a>>::Accessor> {
+
+    type Layout = <T as ArrayField<'a, L, A>>::ArrayLayout;
+    type Accessor = <T as ArrayField<'a, L, A>>::ArrayAccessor;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::Layout, LayoutError> {
+        <T as ArrayField<'a, L, A>>::make_layout(layout_field)
+    }
+
+    unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+        <T as ArrayField<'a, L, A>>::make_accessor(layout, data)
+    }
+
+    fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>> {
+        <T as ArrayField<'a, L, A>>::get_field_spans(layout)
+    }
+
+    fn alignment() -> usize {
+        <T as Field<'a>>::alignment()
+    }
+
+    fn declared_end(layout: &Self::Layout) -> Option<usize> {
+        <T as ArrayField<'a, L, A>>::declared_end(layout)
+    }
+
+    fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+        <T as ArrayField<'a, L, A>>::get_named_field_spans(layout, path)
+    }
+}
+
+impl<'a, T, L, A, OL, OA> ArrayField<'a, OL, OA> for ArrayOf<T, L, A>
+    where T: ArrayField<'a, L, A>,
+        L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<T as Field<'a>>::Accessor>,
+        OL: ArrayHelper<'a, Item=<T as ArrayField<'a, L, A>>::ArrayLayout>,
+        OA: ArrayHelper<'a, Item=<T as ArrayField<'a, L, A>>::ArrayAccessor> {
+
+    type ArrayLayout = NestedArrayFieldLayout<<T as ArrayField<'a, L, A>>::ArrayLayout>;
+    type ArrayAccessor = OA::ArrayType;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
+        if let LayoutInfo::NestedArrayField(inner_info, stride) = layout_field {
+            let inner = <T as ArrayField<'a, L, A>>::make_layout(*inner_info)?;
+            // The inner arrays, including the declared size of their last element, are only moved
+            // by the stride, so they must not overlap
+            if OL::len() > 1 && element_extent::<ArrayOf<T, L, A>>(&inner).1 > stride as usize {
+                return Err(LayoutError);
+            }
+            check_aligned(<T as Field<'a>>::alignment(), &[stride as usize])?;
+            let layout = NestedArrayFieldLayout::new(inner, stride, OL::len());
+            if OL::len() > 0 {
+                check_shift::<Self>(layout.inner(), layout.shift(OL::len() - 1))?;
+            }
+            Ok(layout)
+        } else {
+            Err(LayoutError)
+        }
+    }
+
+    unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
+        let mut helper = OA::uninitialized();
+        {
+            let slice = helper.as_mut_slice();
+            if layout.len() != slice.len() {
+                panic!("dynamiclayout::ArrayField has been misimplemented, layout and accessor lengths mismatch!");
+            }
+            for i in 0..slice.len() {
+                let accessor = <T as ArrayField<'a, L, A>>::make_accessor(layout.inner(), layout.inner_ptr(data, i));
+                ::std::ptr::write(&mut slice[i], accessor);
+            }
+        }
+        helper.into_array()
+    }
+
+    fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item = FieldSpan>> {
+        let spans: Vec<_> = (0..layout.len())
+            .flat_map(|i| {
+                let shift = layout.shift(i);
+                <T as ArrayField<'a, L, A>>::get_field_spans(layout.inner()).map(move |span| shift_span(span, shift))
+            })
+            .collect();
+        Box::new(spans.into_iter())
+    }
+
+    fn declared_end(layout: &Self::ArrayLayout) -> Option<usize> {
+        if layout.len() == 0 {
+            return None;
+        }
+        <T as ArrayField<'a, L, A>>::declared_end(layout.inner()).map(|end| end + layout.shift(layout.len() - 1))
+    }
+
+    fn get_named_field_spans(layout: &Self::ArrayLayout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+        let spans: Vec<_> = (0..layout.len())
+            .flat_map(|i| {
+                let shift = layout.shift(i);
+                <T as ArrayField<'a, L, A>>::get_named_field_spans(layout.inner(), &format!("{}[{}]", path, i))
+                    .map(move |(name, span)| (name, shift_span(span, shift)))
+            })
+            .collect();
+        Box::new(spans.into_iter())
+    }
+}
+
+impl<'a, T, L, A> TrackedField<'a> for ArrayOf<T, L, A>
+    where T: TrackedArrayField<'a, L, A>,
+        L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<T as Field<'a>>::Accessor> {
+
+    type TrackedAccessor = <T as TrackedArrayField<'a, L, A>>::TrackedArrayAccessor;
+
+    unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        <T as TrackedArrayField<'a, L, A>>::make_tracked_accessor(layout, data, shift, tracker)
+    }
+}
+
+impl<'a, T, L, A, OL, OA> TrackedArrayField<'a, OL, OA> for ArrayOf<T, L, A>
+    where T: TrackedArrayField<'a, L, A>,
+        L: ArrayHelper<'a, Item=<T as Field<'a>>::Layout>,
+        A: ArrayHelper<'a, Item=<T as Field<'a>>::Accessor>,
+        OL: ArrayHelper<'a, Item=<T as ArrayField<'a, L, A>>::ArrayLayout>,
+        OA: ArrayHelper<'a, Item=<T as ArrayField<'a, L, A>>::ArrayAccessor> {
+
+    type TrackedArrayAccessor = Vec<<T as TrackedArrayField<'a, L, A>>::TrackedArrayAccessor>;
+
+    unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+        // The shift of the last inner array was checked to fit into OffsetType when the layout was made
+        (0..layout.len())
+            .map(|i| {
+                let inner_shift = shift + layout.shift(i) as OffsetType;
+                <T as TrackedArrayField<'a, L, A>>::make_tracked_accessor(layout.inner(), layout.inner_ptr(data, i), inner_shift, tracker)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,186 @@
+use float_bits::round_shift;
+
+macro_rules! make_normalized_type {
+    ($normalized_type:ident : $storage_type:ty as $wide_type:ty, $to_f32:ident, $from_f32:ident) => (
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+        pub struct $normalized_type($storage_type);
+
+        impl $normalized_type {
+            pub fn from_bits(bits: $storage_type) -> $normalized_type {
+                $normalized_type(bits)
+            }
+
+            pub fn to_bits(self) -> $storage_type {
+                self.0
+            }
+
+            pub fn from_f32(value: f32) -> $normalized_type {
+                $normalized_type($from_f32(value, <$storage_type>::max_value() as $wide_type) as $storage_type)
+            }
+
+            pub fn to_f32(self) -> f32 {
+                $to_f32(self.0 as $wide_type, <$storage_type>::max_value() as $wide_type)
+            }
+
+            pub fn get(&self) -> f32 {
+                self.to_f32()
+            }
+
+            pub fn set(&mut self, value: f32) {
+                *self = $normalized_type::from_f32(value);
+            }
+        }
+
+        impl From<f32> for $normalized_type {
+            fn from(value: f32) -> $normalized_type {
+                $normalized_type::from_f32(value)
+            }
+        }
+
+        impl From<$normalized_type> for f32 {
+            fn from(value: $normalized_type) -> f32 {
+                value.to_f32()
+            }
+        }
+    )
+}
+
+make_normalized_type!(Unorm8: u8 as u32, unorm_to_f32, f32_to_unorm);
+make_normalized_type!(Snorm8: i8 as i32, snorm_to_f32, f32_to_snorm);
+make_normalized_type!(Unorm16: u16 as u32, unorm_to_f32, f32_to_unorm);
+make_normalized_type!(Snorm16: i16 as i32, snorm_to_f32, f32_to_snorm);
+
+/// Unsigned normalized 10-bit red, green and blue with 2-bit alpha in one u32, red in the lowest
+/// bits (`GL_UNSIGNED_INT_2_10_10_10_REV`).
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct R10G10B10A2(u32);
+
+impl R10G10B10A2 {
+    pub fn new(rgba: [f32; 4]) -> R10G10B10A2 {
+        R10G10B10A2(f32_to_unorm(rgba[0], 0x3ff)
+            | f32_to_unorm(rgba[1], 0x3ff) << 10
+            | f32_to_unorm(rgba[2], 0x3ff) << 20
+            | f32_to_unorm(rgba[3], 0x3) << 30)
+    }
+
+    pub fn from_bits(bits: u32) -> R10G10B10A2 {
+        R10G10B10A2(bits)
+    }
+
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn get(&self) -> [f32; 4] {
+        [unorm_to_f32(self.0 & 0x3ff, 0x3ff),
+         unorm_to_f32((self.0 >> 10) & 0x3ff, 0x3ff),
+         unorm_to_f32((self.0 >> 20) & 0x3ff, 0x3ff),
+         unorm_to_f32(self.0 >> 30, 0x3)]
+    }
+
+    pub fn set(&mut self, rgba: [f32; 4]) {
+        *self = R10G10B10A2::new(rgba);
+    }
+}
+
+/// Unsigned 11-bit red and green and 10-bit blue floats in one u32, red in the lowest bits
+/// (`GL_UNSIGNED_INT_10F_11F_11F_REV`). Negative values are stored as zero, and finite values
+/// too large for the format as the largest finite value.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct R11G11B10F(u32);
+
+impl R11G11B10F {
+    pub fn new(rgb: [f32; 3]) -> R11G11B10F {
+        R11G11B10F(f32_to_unsigned_float(rgb[0], 6)
+            | f32_to_unsigned_float(rgb[1], 6) << 11
+            | f32_to_unsigned_float(rgb[2], 5) << 22)
+    }
+
+    pub fn from_bits(bits: u32) -> R11G11B10F {
+        R11G11B10F(bits)
+    }
+
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn get(&self) -> [f32; 3] {
+        [unsigned_float_to_f32(self.0 & 0x7ff, 6),
+         unsigned_float_to_f32((self.0 >> 11) & 0x7ff, 6),
+         unsigned_float_to_f32(self.0 >> 22, 5)]
+    }
+
+    pub fn set(&mut self, rgb: [f32; 3]) {
+        *self = R11G11B10F::new(rgb);
+    }
+}
+
+fn unorm_to_f32(bits: u32, max: u32) -> f32 {
+    bits as f32 / max as f32
+}
+
+// NaN is stored as zero
+fn f32_to_unorm(value: f32, max: u32) -> u32 {
+    if !(value > 0.0) {
+        0
+    } else if value >= 1.0 {
+        max
+    } else {
+        (value * max as f32).round() as u32
+    }
+}
+
+// The most negative value and the one after it both mean -1.0
+fn snorm_to_f32(bits: i32, max: i32) -> f32 {
+    (bits as f32 / max as f32).max(-1.0)
+}
+
+fn f32_to_snorm(value: f32, max: i32) -> i32 {
+    if value.is_nan() {
+        0
+    } else {
+        (value.max(-1.0).min(1.0) * max as f32).round() as i32
+    }
+}
+
+// Like a half without the sign bit, with a 5-bit exponent and fewer mantissa bits
+fn f32_to_unsigned_float(value: f32, mantissa_bits: u32) -> u32 {
+    let infinity = 0x1f << mantissa_bits;
+    if value.is_nan() {
+        return infinity | 1;
+    }
+    if !(value > 0.0) {
+        return 0;
+    }
+    if value.is_infinite() {
+        return infinity;
+    }
+    let bits = value.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x007f_ffff;
+    let result = if exponent >= 0x1f {
+        infinity
+    } else if exponent <= 0 {
+        if exponent < -(mantissa_bits as i32) {
+            return 0;
+        }
+        round_shift(mantissa | 0x0080_0000, 24 - mantissa_bits + (-exponent) as u32)
+    } else {
+        ((exponent as u32) << mantissa_bits) + round_shift(mantissa, 23 - mantissa_bits)
+    };
+    ::std::cmp::min(result, infinity - 1)
+}
+
+fn unsigned_float_to_f32(bits: u32, mantissa_bits: u32) -> f32 {
+    let exponent = bits >> mantissa_bits;
+    let mantissa = bits & ((1 << mantissa_bits) - 1);
+    match exponent {
+        0 => mantissa as f32 * 2f32.powi(-14 - mantissa_bits as i32),
+        0x1f if mantissa == 0 => ::std::f32::INFINITY,
+        0x1f => ::std::f32::NAN,
+        _ => f32::from_bits(((exponent + 127 - 15) << 23) | (mantissa << (23 - mantissa_bits))),
+    }
+}
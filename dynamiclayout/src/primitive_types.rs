@@ -1,18 +1,63 @@
 
-use {Field, ArrayField, LayoutError, ArrayHelper, LengthType, OffsetType};
+use {Field, ArrayField, TrackedField, TrackedArrayField, LayoutError, ArrayHelper, LengthType, OffsetType, Pod, check_aligned};
 use load::{LayoutInfo, FieldSpan};
 use layout::{SimpleFieldLayout, ArrayFieldLayout};
 use accessor::PrimitiveArrayAccessor;
+use tracking::{DirtyTracker, TrackedValue, TrackedArrayAccessor};
 use vector_types::*;
+use half::F16;
+use handles::{TextureHandle, ImageHandle, DevicePointer};
+use normalized::*;
+
+/// GLSL `bool`, which takes 4 bytes like the other scalars. Any nonzero value reads as true.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Bool32(u32);
+
+impl Bool32 {
+    pub fn new(value: bool) -> Bool32 {
+        Bool32(value as u32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, value: bool) {
+        self.0 = value as u32;
+    }
+}
+
+impl PartialEq for Bool32 {
+    fn eq(&self, other: &Bool32) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for Bool32 {}
+
+impl From<bool> for Bool32 {
+    fn from(value: bool) -> Bool32 {
+        Bool32::new(value)
+    }
+}
+
+impl From<Bool32> for bool {
+    fn from(value: Bool32) -> bool {
+        value.get()
+    }
+}
 
 macro_rules! impl_primitive_type {
-    ($primitive_type:ty) => (
-        impl<'a> Field<'a> for $primitive_type {
+    // Generic types list their parameters in brackets, bounded by the accessor lifetime 'a
+    (impl [$($generics:tt)*] $primitive_type:ty : $scalar_type:ty) => (
+        impl<'a, $($generics)*> Field<'a> for $primitive_type {
             type Layout = SimpleFieldLayout;
             type Accessor = &'a mut $primitive_type;
 
             fn make_layout(layout_field: LayoutInfo) -> Result<Self::Layout, LayoutError> {
                 if let LayoutInfo::PrimitiveField(offset) = layout_field {
+                    check_aligned(::std::mem::align_of::<$scalar_type>(), &[offset as usize])?;
                     Ok(SimpleFieldLayout::new(offset))
                 } else {
                     Err(LayoutError)
@@ -20,8 +65,11 @@ macro_rules! impl_primitive_type {
             }
 
             unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> &'a mut $primitive_type {
-                let ptr = layout.offset_ptr(data);
-                &mut *(ptr as *mut $primitive_type)
+                &mut *(layout.offset_ptr(data) as *mut $primitive_type)
+            }
+
+            fn alignment() -> usize {
+                ::std::mem::align_of::<$scalar_type>()
             }
 
             fn get_field_spans(layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>> {
@@ -33,7 +81,7 @@ macro_rules! impl_primitive_type {
             }
         }
 
-        impl<'a, L, A> ArrayField<'a, L, A> for $primitive_type
+        impl<'a, L, A, $($generics)*> ArrayField<'a, L, A> for $primitive_type
             where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
                 A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
 
@@ -42,6 +90,7 @@ macro_rules! impl_primitive_type {
 
             fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
                  if let LayoutInfo::ArrayField(offset, stride) = layout_field {
+                    check_aligned(::std::mem::align_of::<$scalar_type>(), &[offset as usize, stride as usize])?;
                     Ok(ArrayFieldLayout::new(offset, stride))
                 } else {
                     Err(LayoutError)
@@ -49,8 +98,7 @@ macro_rules! impl_primitive_type {
             }
 
             unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
-                let ptr = data.offset(layout.offset() as isize);
-                PrimitiveArrayAccessor::new(ptr, layout.stride(), A::len())
+                PrimitiveArrayAccessor::new(data.offset(layout.offset() as isize), layout.stride(), A::len())
             }
 
             fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item = FieldSpan>> {
@@ -63,22 +111,158 @@ macro_rules! impl_primitive_type {
             }
         }
 
+        impl<'a, $($generics)*> TrackedField<'a> for $primitive_type {
+            type TrackedAccessor = TrackedValue<'a, $primitive_type>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+                let value = <$primitive_type as Field<'a>>::make_accessor(layout, data);
+                TrackedValue::new(value, layout.offset() + shift, tracker)
+            }
+        }
+
+        impl<'a, L, A, $($generics)*> TrackedArrayField<'a, L, A> for $primitive_type
+            where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+                A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> {
+
+            type TrackedArrayAccessor = TrackedArrayAccessor<'a, $primitive_type>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+                let inner = <$primitive_type as ArrayField<'a, L, A>>::make_accessor(layout, data);
+                TrackedArrayAccessor::new(inner, layout.offset() + shift, layout.stride(), tracker)
+            }
+        }
+
+    );
+    // Vectors are packed, but their components are indexed through references, so they need the
+    // alignment of the component type
+    ($primitive_type:ty : $scalar_type:ty) => (
+        impl_primitive_type!(impl [] $primitive_type : $scalar_type);
+    );
+    ($primitive_type:ty) => (
+        impl_primitive_type!(impl [] $primitive_type : $primitive_type);
     )
 }
 
+macro_rules! impl_pod_array {
+    ($($len:expr),+) => (
+        $( unsafe impl<T: Pod> Pod for [T; $len] {} )+
+    )
+}
+
+impl_pod_array!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+                17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,
+                64, 128, 256, 512, 1024);
+
+unsafe impl Pod for f32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for f64 {}
+unsafe impl Pod for Bool32 {}
+unsafe impl Pod for F16 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u8 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for TextureHandle {}
+unsafe impl Pod for ImageHandle {}
+unsafe impl<T> Pod for DevicePointer<T> {}
+unsafe impl Pod for Unorm8 {}
+unsafe impl Pod for Snorm8 {}
+unsafe impl Pod for Unorm16 {}
+unsafe impl Pod for Snorm16 {}
+unsafe impl Pod for R10G10B10A2 {}
+unsafe impl Pod for R11G11B10F {}
 
 impl_primitive_type!(f32);
 impl_primitive_type!(i32);
 impl_primitive_type!(u32);
+impl_primitive_type!(f64);
+impl_primitive_type!(Bool32);
+impl_primitive_type!(F16);
+impl_primitive_type!(i16);
+impl_primitive_type!(u16);
+impl_primitive_type!(i8);
+impl_primitive_type!(u8);
+impl_primitive_type!(i64);
+impl_primitive_type!(u64);
+impl_primitive_type!(TextureHandle);
+impl_primitive_type!(ImageHandle);
+impl_primitive_type!(impl [T: 'a] DevicePointer<T> : u64);
+impl_primitive_type!(Unorm8);
+impl_primitive_type!(Snorm8);
+impl_primitive_type!(Unorm16);
+impl_primitive_type!(Snorm16);
+impl_primitive_type!(R10G10B10A2);
+impl_primitive_type!(R11G11B10F);
+
+impl_primitive_type!(Vec2: f32);
+impl_primitive_type!(IVec2: i32);
+impl_primitive_type!(UVec2: u32);
+
+impl_primitive_type!(Vec3: f32);
+impl_primitive_type!(IVec3: i32);
+impl_primitive_type!(UVec3: u32);
+
+impl_primitive_type!(Vec4: f32);
+impl_primitive_type!(IVec4: i32);
+impl_primitive_type!(UVec4: u32);
+
+impl_primitive_type!(DVec2: f64);
+impl_primitive_type!(DVec3: f64);
+impl_primitive_type!(DVec4: f64);
+
+impl_primitive_type!(BVec2: Bool32);
+impl_primitive_type!(BVec3: Bool32);
+impl_primitive_type!(BVec4: Bool32);
+
+impl_primitive_type!(F16Vec2: F16);
+impl_primitive_type!(F16Vec3: F16);
+impl_primitive_type!(F16Vec4: F16);
+
+impl_primitive_type!(I16Vec2: i16);
+impl_primitive_type!(I16Vec3: i16);
+impl_primitive_type!(I16Vec4: i16);
+
+impl_primitive_type!(U16Vec2: u16);
+impl_primitive_type!(U16Vec3: u16);
+impl_primitive_type!(U16Vec4: u16);
+
+impl_primitive_type!(I8Vec2: i8);
+impl_primitive_type!(I8Vec3: i8);
+impl_primitive_type!(I8Vec4: i8);
+
+impl_primitive_type!(U8Vec2: u8);
+impl_primitive_type!(U8Vec3: u8);
+impl_primitive_type!(U8Vec4: u8);
+
+impl_primitive_type!(I64Vec2: i64);
+impl_primitive_type!(I64Vec3: i64);
+impl_primitive_type!(I64Vec4: i64);
+
+impl_primitive_type!(U64Vec2: u64);
+impl_primitive_type!(U64Vec3: u64);
+impl_primitive_type!(U64Vec4: u64);
+
+impl_primitive_type!(Unorm8Vec2: Unorm8);
+impl_primitive_type!(Unorm8Vec3: Unorm8);
+impl_primitive_type!(Unorm8Vec4: Unorm8);
 
-impl_primitive_type!(Vec2);
-impl_primitive_type!(IVec2);
-impl_primitive_type!(UVec2);
+impl_primitive_type!(Snorm8Vec2: Snorm8);
+impl_primitive_type!(Snorm8Vec3: Snorm8);
+impl_primitive_type!(Snorm8Vec4: Snorm8);
 
-impl_primitive_type!(Vec3);
-impl_primitive_type!(IVec3);
-impl_primitive_type!(UVec3);
+impl_primitive_type!(Unorm16Vec2: Unorm16);
+impl_primitive_type!(Unorm16Vec3: Unorm16);
+impl_primitive_type!(Unorm16Vec4: Unorm16);
 
-impl_primitive_type!(Vec4);
-impl_primitive_type!(IVec4);
-impl_primitive_type!(UVec4);
+impl_primitive_type!(Snorm16Vec2: Snorm16);
+impl_primitive_type!(Snorm16Vec3: Snorm16);
+impl_primitive_type!(Snorm16Vec4: Snorm16);
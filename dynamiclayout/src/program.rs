@@ -0,0 +1,118 @@
+use {DynamicLayout, LayoutError};
+use load::{LayoutInfo, LoadStructLayout};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlockKind {
+    Uniform,
+    Storage,
+}
+
+/// Reflection data of one interface block: its field layout, where it is bound and how large
+/// the driver says it is (`GL_UNIFORM_BLOCK_DATA_SIZE` for uniform blocks, `GL_BUFFER_DATA_SIZE`
+/// for storage blocks, or the size of the block type in SPIR-V).
+pub struct BlockDescriptor<'a> {
+    pub name: String,
+    pub kind: BlockKind,
+    pub set: u32,
+    pub binding: u32,
+    pub data_size: usize,
+    pub layout: &'a LoadStructLayout,
+}
+
+impl<'a> LoadStructLayout for BlockDescriptor<'a> {
+    fn get_field_layout(&self, field_name: &str) -> Option<LayoutInfo> {
+        self.layout.get_field_layout(field_name)
+    }
+
+    fn declared_size(&self) -> Option<usize> {
+        Some(self.data_size)
+    }
+}
+
+/// Layout of a derived block type together with the binding it was loaded for.
+#[derive(Debug)]
+pub struct BlockLayout<L> {
+    layout: L,
+    kind: BlockKind,
+    set: u32,
+    binding: u32,
+    data_size: usize,
+}
+
+impl<L> BlockLayout<L> {
+    /// Fails if the fields of `T` do not match the block, or do not fit into its data size.
+    pub fn load<'a, T>(block: &BlockDescriptor) -> Result<BlockLayout<L>, LayoutError>
+            where T: DynamicLayout<'a, Layout = L> {
+        let layout = T::load_layout(block)?;
+        if T::required_data_len(&layout) > block.data_size {
+            return Err(LayoutError);
+        }
+        Ok(BlockLayout {
+            layout,
+            kind: block.kind,
+            set: block.set,
+            binding: block.binding,
+            data_size: block.data_size,
+        })
+    }
+
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    pub fn kind(&self) -> BlockKind {
+        self.kind
+    }
+
+    pub fn set(&self) -> u32 {
+        self.set
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+}
+
+/// The blocks of one reflected program, looked up by block name.
+#[derive(Default)]
+pub struct ProgramLayout<'a> {
+    blocks: Vec<BlockDescriptor<'a>>,
+}
+
+impl<'a> ProgramLayout<'a> {
+    pub fn new() -> ProgramLayout<'a> {
+        ProgramLayout { blocks: Vec::new() }
+    }
+
+    /// Blocks are identified both by name and by kind, set and binding, so adding a block replaces
+    /// any block with the same name or bound at the same place. Uniform and storage blocks have
+    /// separate binding points in GL, so they don't replace each other.
+    pub fn add_block(&mut self, block: BlockDescriptor<'a>) {
+        self.blocks.retain(|existing| {
+            existing.name != block.name
+                && (existing.kind, existing.set, existing.binding) != (block.kind, block.set, block.binding)
+        });
+        self.blocks.push(block);
+    }
+
+    pub fn blocks(&self) -> &[BlockDescriptor<'a>] {
+        &self.blocks
+    }
+
+    pub fn block(&self, name: &str) -> Option<&BlockDescriptor<'a>> {
+        self.blocks.iter().find(|block| block.name == name)
+    }
+
+    pub fn block_at(&self, kind: BlockKind, set: u32, binding: u32) -> Option<&BlockDescriptor<'a>> {
+        self.blocks.iter().find(|block| block.kind == kind && block.set == set && block.binding == binding)
+    }
+
+    /// Loads the layout of `T` from the block with the given name, an error if the program has no such block.
+    pub fn load_block<'b, T: DynamicLayout<'b>>(&self, name: &str) -> Result<BlockLayout<T::Layout>, LayoutError> {
+        BlockLayout::load::<T>(self.block(name).ok_or(LayoutError)?)
+    }
+}
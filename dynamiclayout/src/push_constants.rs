@@ -0,0 +1,131 @@
+use std::ops::BitOr;
+use {DynamicLayout, AccessorError};
+use buffer::AlignedBuffer;
+
+// maxPushConstantsSize is at least this large on every Vulkan implementation
+pub const DEFAULT_PUSH_CONSTANT_LIMIT: usize = 128;
+
+/// Shader stages that can read a range, with the bits of `VkShaderStageFlagBits`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderStages(pub u32);
+
+impl ShaderStages {
+    pub const VERTEX: ShaderStages = ShaderStages(0x01);
+    pub const TESSELLATION_CONTROL: ShaderStages = ShaderStages(0x02);
+    pub const TESSELLATION_EVALUATION: ShaderStages = ShaderStages(0x04);
+    pub const GEOMETRY: ShaderStages = ShaderStages(0x08);
+    pub const FRAGMENT: ShaderStages = ShaderStages(0x10);
+    pub const COMPUTE: ShaderStages = ShaderStages(0x20);
+    pub const ALL_GRAPHICS: ShaderStages = ShaderStages(0x1f);
+
+    pub fn intersects(&self, other: ShaderStages) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for ShaderStages {
+    type Output = ShaderStages;
+
+    fn bitor(self, other: ShaderStages) -> ShaderStages {
+        ShaderStages(self.0 | other.0)
+    }
+}
+
+// Same fields as VkPushConstantRange
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PushConstantRange {
+    pub stages: ShaderStages,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushConstantError {
+    // The ranges would need more bytes than the limit
+    LimitExceeded { required_len: usize, limit: usize },
+    // The stages that are already used by other ranges
+    StagesOverlap(ShaderStages),
+}
+
+/// Packs several blocks into one push constant range. Each block gets its own sub-range,
+/// starting at a multiple of 4 bytes or of the block's alignment if that is larger, and the
+/// layouts are loaded relative to the start of it.
+///
+/// Vulkan allows a stage in only one range, so the stages of the blocks must not overlap.
+pub struct PushConstants {
+    limit: usize,
+    ranges: Vec<PushConstantRange>,
+    buffer: AlignedBuffer,
+}
+
+impl PushConstants {
+    pub fn new() -> PushConstants {
+        PushConstants::with_limit(DEFAULT_PUSH_CONSTANT_LIMIT)
+    }
+
+    pub fn with_limit(limit: usize) -> PushConstants {
+        PushConstants {
+            limit,
+            ranges: Vec::new(),
+            buffer: AlignedBuffer::new(),
+        }
+    }
+
+    /// Adds a block and returns the index of its range.
+    pub fn add<'a, T: DynamicLayout<'a>>(&mut self, layout: &T::Layout, stages: ShaderStages) -> Result<usize, PushConstantError> {
+        let used = self.ranges.iter().fold(ShaderStages(0), |used, range| used | range.stages);
+        if used.intersects(stages) {
+            return Err(PushConstantError::StagesOverlap(ShaderStages(used.0 & stages.0)));
+        }
+        let alignment = ::std::cmp::max(4, T::required_alignment(layout));
+        let offset = (self.buffer.len() + alignment - 1) / alignment * alignment;
+        let size = (T::required_data_len(layout) + 3) & !3;
+        if offset + size > self.limit {
+            return Err(PushConstantError::LimitExceeded { required_len: offset + size, limit: self.limit });
+        }
+        self.buffer.resize(offset + size);
+        self.ranges.push(PushConstantRange { stages, offset: offset as u32, size: size as u32 });
+        Ok(self.ranges.len() - 1)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn ranges(&self) -> &[PushConstantRange] {
+        &self.ranges
+    }
+
+    /// Accessor for the block of range `index`. If there is no such range the error has a data length of 0.
+    pub fn accessor<'b, T: DynamicLayout<'b>>(&'b mut self, index: usize, layout: &T::Layout) -> Result<T::Accessor, AccessorError> {
+        let error = AccessorError {
+            required_data_len: T::required_data_len(layout),
+            data_len: 0,
+            required_alignment: T::required_alignment(layout),
+        };
+        let range = match self.ranges.get(index) {
+            Some(range) => range.offset as usize..(range.offset + range.size) as usize,
+            None => return Err(error),
+        };
+        match self.buffer.data().sub(range) {
+            Some(data) => T::make_accessor(layout, data),
+            None => Err(error),
+        }
+    }
+
+    /// The bytes of all ranges, for `vkCmdPushConstants` with offset 0.
+    pub fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    /// The bytes of one range, for `vkCmdPushConstants` with the offset of that range.
+    pub fn range_slice(&self, index: usize) -> Option<&[u8]> {
+        self.ranges.get(index).map(|range| &self.as_slice()[range.offset as usize..(range.offset + range.size) as usize])
+    }
+}
+
+impl Default for PushConstants {
+    fn default() -> PushConstants {
+        PushConstants::new()
+    }
+}
@@ -0,0 +1,99 @@
+use std::marker::PhantomData;
+use {Field, TrackedField, LayoutError, OffsetType, LengthType, Pod, element_extent, check_aligned};
+use load::{LayoutInfo, FieldSpan};
+use layout::RuntimeArrayFieldLayout;
+use tracking::DirtyTracker;
+
+/// Marker for a runtime-sized array (`Particle particles[];`), allowed as the last field of a
+/// shader storage block. The element count of the accessor is computed from the data length.
+///
+/// Written ranges are recorded as `FieldSpan`s, so tracked accessors only reach the elements
+/// that end within the range of `OffsetType`.
+pub struct RuntimeArray<T>(PhantomData<T>);
+
+impl<T> Clone for RuntimeArray<T> {
+    fn clone(&self) -> RuntimeArray<T> {
+        RuntimeArray(PhantomData)
+    }
+}
+
+impl<T> Copy for RuntimeArray<T> {}
+
+impl<T> ::std::fmt::Debug for RuntimeArray<T> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.write_str("RuntimeArray")
+    }
+}
+
+// Zero-sized, so there are no bytes that could be padding
+unsafe impl<T: Pod> Pod for RuntimeArray<T> {}
+
+impl<'a, T: Field<'a>> Field<'a> for RuntimeArray<T> {
+    type Layout = RuntimeArrayFieldLayout<T::Layout>;
+    type Accessor = Vec<T::Accessor>;
+
+    fn make_layout(layout_field: LayoutInfo) -> Result<Self::Layout, LayoutError> {
+        if let LayoutInfo::RuntimeArrayField(element_info, stride) = layout_field {
+            let element = T::make_layout(*element_info)?;
+            let (start, element_len) = element_extent::<T>(&element);
+            if element_len > stride as usize || T::runtime_array_offset(&element).is_some() {
+                return Err(LayoutError);
+            }
+            check_aligned(T::alignment(), &[stride as usize])?;
+            Ok(RuntimeArrayFieldLayout::new(element, start, element_len as LengthType, stride))
+        } else {
+            Err(LayoutError)
+        }
+    }
+
+    unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+        Self::make_accessor_with_len(layout, data, 0)
+    }
+
+    // The element layout has offsets relative to the start of the block, so moving the
+    // data pointer by the stride gives the accessor of the next element.
+    unsafe fn make_accessor_with_len(layout: &Self::Layout, data: *mut u8, data_len: usize) -> Self::Accessor {
+        (0..layout.element_count(data_len))
+            .map(|i| T::make_accessor(layout.element(), layout.element_ptr(data, i)))
+            .collect()
+    }
+
+    // The array has no fixed size, so it does not contribute to the required data length
+    fn get_field_spans(_layout: &Self::Layout) -> Box<Iterator<Item = FieldSpan>> {
+        Box::new(::std::iter::empty())
+    }
+
+    fn alignment() -> usize {
+        T::alignment()
+    }
+
+    fn runtime_array_offset(layout: &Self::Layout) -> Option<OffsetType> {
+        Some(layout.offset())
+    }
+}
+
+impl<'a, T: TrackedField<'a>> TrackedField<'a> for RuntimeArray<T> {
+    type TrackedAccessor = Vec<T::TrackedAccessor>;
+
+    unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                    data: *mut u8,
+                                    shift: OffsetType,
+                                    tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        Self::make_tracked_accessor_with_len(layout, data, shift, 0, tracker)
+    }
+
+    // Written ranges are marked as FieldSpans, so only the elements that end within OffsetType are tracked
+    unsafe fn make_tracked_accessor_with_len(layout: &Self::Layout,
+                                             data: *mut u8,
+                                             shift: OffsetType,
+                                             data_len: usize,
+                                             tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+        let trackable_len = ::std::cmp::min(data_len, (OffsetType::max_value() - shift) as usize);
+        (0..layout.element_count(trackable_len))
+            .map(|i| {
+                let element_shift = shift + (layout.stride() as usize * i) as OffsetType;
+                T::make_tracked_accessor(layout.element(), layout.element_ptr(data, i), element_shift, tracker)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,62 @@
+use {DynamicLayout, AccessorError};
+use load::FieldSpan;
+use buffer::AlignedBuffer;
+use tracking::DirtyTracker;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub path: String,
+    pub span: FieldSpan,
+}
+
+/// Keeps a CPU-side copy of a block. Values are written through the tracked accessor returned by
+/// `accessor`, which compares them to the copy and marks only the bytes that change, so writing a
+/// value that is already there is a no-op. `take_changes` reports the fields that were changed.
+pub struct ShadowBlock {
+    fields: Vec<(String, FieldSpan)>,
+    shadow: AlignedBuffer,
+    tracker: DirtyTracker,
+    invalidated: bool,
+}
+
+impl ShadowBlock {
+    pub fn new<'a, T: DynamicLayout<'a>>(layout: &T::Layout) -> ShadowBlock {
+        ShadowBlock {
+            fields: T::get_named_field_spans(layout).collect(),
+            shadow: AlignedBuffer::for_layout::<T>(layout),
+            tracker: DirtyTracker::comparing(),
+            invalidated: true,
+        }
+    }
+
+    pub fn accessor<'b, T: DynamicLayout<'b>>(&'b mut self, layout: &T::Layout) -> Result<T::TrackedAccessor, AccessorError> {
+        let ShadowBlock { ref mut shadow, ref tracker, .. } = *self;
+        T::make_tracked_accessor(layout, shadow.data(), tracker)
+    }
+
+    /// The fields that were changed since the last call, everything after creation or `invalidate`.
+    pub fn take_changes(&mut self) -> Vec<FieldChange> {
+        let dirty = self.tracker.take();
+        let invalidated = self.invalidated;
+        self.invalidated = false;
+        self.fields.iter()
+            .filter(|&&(_, span)| invalidated || dirty.spans().iter().any(|&written| overlaps(written, span)))
+            .map(|&(ref path, span)| FieldChange { path: path.clone(), span })
+            .collect()
+    }
+
+    /// Makes the next `take_changes` report every field, for example after the GPU buffer was recreated.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// The current bytes of the block, to upload the changed ranges from.
+    pub fn as_slice(&self) -> &[u8] {
+        self.shadow.as_slice()
+    }
+}
+
+fn overlaps(a: FieldSpan, b: FieldSpan) -> bool {
+    (a.offset as usize) < b.offset as usize + b.length as usize
+        && (b.offset as usize) < a.offset as usize + a.length as usize
+}
@@ -0,0 +1,77 @@
+use Pod;
+use primitive_types::Bool32;
+
+/// Scalar types allowed as specialization constants. `Bool32` is used for `bool` constants.
+pub trait SpecializationConstant: Pod {}
+
+impl SpecializationConstant for Bool32 {}
+impl SpecializationConstant for i32 {}
+impl SpecializationConstant for u32 {}
+impl SpecializationConstant for f32 {}
+impl SpecializationConstant for f64 {}
+impl SpecializationConstant for i64 {}
+impl SpecializationConstant for u64 {}
+impl SpecializationConstant for i16 {}
+impl SpecializationConstant for u16 {}
+impl SpecializationConstant for i8 {}
+impl SpecializationConstant for u8 {}
+
+// Same fields as VkSpecializationMapEntry
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SpecializationMapEntry {
+    pub constant_id: u32,
+    pub offset: u32,
+    pub size: usize,
+}
+
+#[derive(Debug)]
+pub struct DuplicateConstantError {
+    pub constant_id: u32,
+}
+
+/// Map entries and the data they point into, for `VkSpecializationInfo`.
+///
+/// Every constant is placed at the next offset aligned to its own size, in the order they are pushed.
+#[derive(Debug, Clone, Default)]
+pub struct SpecializationInfo {
+    entries: Vec<SpecializationMapEntry>,
+    data: Vec<u8>,
+}
+
+impl SpecializationInfo {
+    pub fn new() -> SpecializationInfo {
+        SpecializationInfo::default()
+    }
+
+    /// Fails if a value for the constant has already been pushed.
+    pub fn push<T: SpecializationConstant>(&mut self, constant_id: u32, value: T) -> Result<(), DuplicateConstantError> {
+        if self.entry(constant_id).is_some() {
+            return Err(DuplicateConstantError { constant_id });
+        }
+        let size = ::std::mem::size_of::<T>();
+        let offset = (self.data.len() + size - 1) / size * size;
+        let bytes = unsafe { ::std::slice::from_raw_parts(&value as *const T as *const u8, size) };
+        self.data.resize(offset, 0);
+        self.data.extend_from_slice(bytes);
+        self.entries.push(SpecializationMapEntry { constant_id, offset: offset as u32, size });
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[SpecializationMapEntry] {
+        &self.entries
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn entry(&self, constant_id: u32) -> Option<&SpecializationMapEntry> {
+        self.entries.iter().find(|entry| entry.constant_id == constant_id)
+    }
+}
+
+/// Structs of specialization constants, usually implemented with `#[derive(SpecializationConstants)]`
+/// and a `#[dynamiclayout(constant_id = N)]` attribute on every field.
+pub trait SpecializationConstants {
+    fn specialization_info(&self) -> SpecializationInfo;
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::ops::{Deref, Index};
+use {OffsetType, StrideType, LengthType};
+use load::FieldSpan;
+use accessor::PrimitiveArrayAccessor;
+
+/// Sorted set of written byte ranges. Overlapping and adjacent ranges are merged,
+/// so the spans are always the minimal set that covers every write.
+#[derive(Debug, Default)]
+pub struct DirtyRanges {
+    spans: Vec<FieldSpan>,
+}
+
+impl DirtyRanges {
+    pub fn new() -> DirtyRanges {
+        DirtyRanges { spans: Vec::new() }
+    }
+
+    pub fn mark(&mut self, span: FieldSpan) {
+        if span.length == 0 {
+            return;
+        }
+        let mut start = span.offset;
+        let mut end = span.offset + span.length;
+        let first = self.spans.iter().position(|s| s.offset + s.length >= start).unwrap_or(self.spans.len());
+        let mut last = first;
+        while last < self.spans.len() && self.spans[last].offset <= end {
+            let s = &self.spans[last];
+            start = ::std::cmp::min(start, s.offset);
+            end = ::std::cmp::max(end, s.offset + s.length);
+            last += 1;
+        }
+        self.spans.splice(first..last, Some(FieldSpan::new(start, end - start)));
+    }
+
+    pub fn spans(&self) -> &[FieldSpan] {
+        &self.spans
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.spans.clear();
+    }
+}
+
+/// Shared by all the accessors of one tracked accessor tree, collects the ranges they write.
+#[derive(Debug, Default)]
+pub struct DirtyTracker {
+    ranges: RefCell<DirtyRanges>,
+    compare: bool,
+}
+
+impl DirtyTracker {
+    pub fn new() -> DirtyTracker {
+        DirtyTracker { ranges: RefCell::new(DirtyRanges::new()), compare: false }
+    }
+
+    /// A tracker for which `set` compares the new value to the old one, and only marks the bytes
+    /// that differ. Writes through `get_mut` still mark the whole value.
+    pub fn comparing() -> DirtyTracker {
+        DirtyTracker { ranges: RefCell::new(DirtyRanges::new()), compare: true }
+    }
+
+    pub fn mark(&self, span: FieldSpan) {
+        self.ranges.borrow_mut().mark(span);
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.ranges.borrow().is_empty()
+    }
+
+    /// Returns the ranges written so far and starts over with an empty set.
+    pub fn take(&self) -> DirtyRanges {
+        ::std::mem::replace(&mut *self.ranges.borrow_mut(), DirtyRanges::new())
+    }
+
+    fn write<T>(&self, dest: &mut T, value: T, offset: OffsetType) {
+        let len = ::std::mem::size_of::<T>();
+        if !self.compare {
+            *dest = value;
+            self.mark(FieldSpan::new(offset, len as LengthType));
+            return;
+        }
+        // The accessed types are all plain data without padding, so their bytes can be compared
+        let changed = {
+            let old = unsafe { ::std::slice::from_raw_parts(dest as *const T as *const u8, len) };
+            let new = unsafe { ::std::slice::from_raw_parts(&value as *const T as *const u8, len) };
+            let first = old.iter().zip(new).position(|(a, b)| a != b);
+            let last = old.iter().zip(new).rposition(|(a, b)| a != b);
+            first.and_then(|first| last.map(|last| (first, last)))
+        };
+        *dest = value;
+        if let Some((first, last)) = changed {
+            self.mark(FieldSpan::new(offset + first as OffsetType, (last - first + 1) as LengthType));
+        }
+    }
+}
+
+pub struct TrackedValue<'a, T: 'a> {
+    value: &'a mut T,
+    offset: OffsetType,
+    tracker: &'a DirtyTracker,
+}
+
+impl<'a, T: 'a> TrackedValue<'a, T> {
+    pub fn new(value: &'a mut T, offset: OffsetType, tracker: &'a DirtyTracker) -> TrackedValue<'a, T> {
+        TrackedValue { value, offset, tracker }
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.tracker.write(self.value, value, self.offset);
+    }
+
+    // The whole value is considered written, even if only a part of it is changed through the reference.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.tracker.mark(FieldSpan::new(self.offset, ::std::mem::size_of::<T>() as LengthType));
+        self.value
+    }
+}
+
+impl<'a, T: 'a> Deref for TrackedValue<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+pub struct TrackedArrayAccessor<'a, T: 'a> {
+    inner: PrimitiveArrayAccessor<'a, T>,
+    offset: OffsetType,
+    stride: StrideType,
+    tracker: &'a DirtyTracker,
+}
+
+impl<'a, T: 'a> TrackedArrayAccessor<'a, T> {
+    pub fn new(inner: PrimitiveArrayAccessor<'a, T>,
+               offset: OffsetType,
+               stride: StrideType,
+               tracker: &'a DirtyTracker) -> TrackedArrayAccessor<'a, T> {
+        TrackedArrayAccessor { inner, offset, stride, tracker }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        let span = self.element_span(index);
+        self.tracker.write(&mut self.inner[index], value, span.offset);
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut T {
+        let span = self.element_span(index);
+        self.tracker.mark(span);
+        &mut self.inner[index]
+    }
+
+    fn element_span(&self, index: usize) -> FieldSpan {
+        // Index first, so that an out of bounds access panics before anything gets marked
+        let _ = &self.inner[index];
+        element_span(self.offset, self.stride, index, ::std::mem::size_of::<T>())
+    }
+}
+
+impl<'a, T: 'a> Index<usize> for TrackedArrayAccessor<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.inner[index]
+    }
+}
+
+/// Span of the element `index` of an array that starts at `offset`. Panics if the element does
+/// not fit into `OffsetType`, rather than marking the wrong range.
+pub fn element_span(offset: OffsetType, stride: StrideType, index: usize, size: usize) -> FieldSpan {
+    FieldSpan::new(offset, size as LengthType)
+        .shifted(stride as usize * index)
+        .expect("tracked array element does not fit into OffsetType")
+}
@@ -0,0 +1,240 @@
+use std::marker::PhantomData;
+use {LayoutError, LocationType};
+use load::{LayoutInfo, LoadStructLayout};
+use layout::{LocationFieldLayout, LocationArrayFieldLayout};
+use primitive_types::Bool32;
+use handles::{TextureHandle, ImageHandle};
+use vector_types::*;
+use matrix_types::*;
+
+/// Value of one `glUniform*` call. Vectors are stored as their components and matrices in
+/// column-major order, so the number of values tells which variant of the call to use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformValue {
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    // Also used for booleans
+    Int(Vec<i32>),
+    Uint(Vec<u32>),
+    // Column count, row count and values
+    Matrix(u8, u8, Vec<f32>),
+    DMatrix(u8, u8, Vec<f64>),
+    // glUniformHandleui64ARB
+    Handle(u64),
+}
+
+/// Receives the values written through uniform accessors, usually by calling `glUniform*`
+/// on the current program. It is shared by all the accessors of a struct, so it takes `&self`.
+pub trait UniformSink {
+    fn set(&self, location: LocationType, value: UniformValue);
+}
+
+/// Types that can be the value of a default-block uniform.
+pub trait UniformType: Copy {
+    fn uniform_value(&self) -> UniformValue;
+}
+
+/// Default-block uniform counterpart of `Field`. Layouts are loaded from `LayoutInfo::LocationField`
+/// for values and `StructField` for structs, accessors send every write to a `UniformSink`.
+///
+/// `#[derive(DynamicLayout)]` implements this for structs marked with `#[dynamiclayout(default_block)]`.
+pub trait UniformField<'a> {
+    type UniformLayout;
+    type UniformAccessor: 'a;
+
+    fn make_uniform_layout(layout_field: LayoutInfo) -> Result<Self::UniformLayout, LayoutError>;
+
+    fn make_uniform_accessor(layout: &Self::UniformLayout, sink: &'a UniformSink) -> Self::UniformAccessor;
+}
+
+pub trait UniformArrayField<'a> : UniformField<'a> {
+    type UniformArrayLayout;
+    type UniformArrayAccessor: 'a;
+
+    fn make_uniform_array_layout(layout_field: LayoutInfo, len: usize) -> Result<Self::UniformArrayLayout, LayoutError>;
+
+    fn make_uniform_array_accessor(layout: &Self::UniformArrayLayout, sink: &'a UniformSink) -> Self::UniformArrayAccessor;
+}
+
+pub fn load_uniform_layout<'a, T: UniformField<'a>>(layout_info: &LoadStructLayout) -> Result<T::UniformLayout, LayoutError> {
+    T::make_uniform_layout(LayoutInfo::StructField(layout_info))
+}
+
+// Every element of an array of structs has its own locations
+pub fn make_uniform_struct_array_layout<'a, T>(layout_field: LayoutInfo, len: usize) -> Result<Vec<T::UniformLayout>, LayoutError>
+        where T: UniformField<'a> {
+    if let LayoutInfo::StructArrayField(elements) = layout_field {
+        if elements.len() != len {
+            return Err(LayoutError);
+        }
+        elements.iter().map(|element| T::make_uniform_layout(LayoutInfo::StructField(*element))).collect()
+    } else {
+        Err(LayoutError)
+    }
+}
+
+pub struct UniformAccessor<'a, T> {
+    location: LocationType,
+    sink: &'a UniformSink,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: UniformType> UniformAccessor<'a, T> {
+    pub fn location(&self) -> LocationType {
+        self.location
+    }
+
+    pub fn set(&self, value: T) {
+        self.sink.set(self.location, value.uniform_value());
+    }
+}
+
+pub struct UniformArrayAccessor<'a, T> {
+    location: LocationType,
+    len: usize,
+    sink: &'a UniformSink,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: UniformType> UniformArrayAccessor<'a, T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn location(&self, index: usize) -> LocationType {
+        assert!(index < self.len, "dynamiclayout: uniform array index {} is out of bounds for length {}", index, self.len);
+        self.location + index as LocationType
+    }
+
+    pub fn set(&self, index: usize, value: T) {
+        self.sink.set(self.location(index), value.uniform_value());
+    }
+}
+
+// Vectors and matrices are tightly packed, so their components can be read one after the other
+fn components<T, S: Copy>(value: &T) -> Vec<S> {
+    let count = ::std::mem::size_of::<T>() / ::std::mem::size_of::<S>();
+    let ptr = value as *const T as *const S;
+    (0..count).map(|i| unsafe { ::std::ptr::read_unaligned(ptr.offset(i as isize)) }).collect()
+}
+
+macro_rules! impl_uniform_field {
+    ($uniform_type:ty) => (
+        impl<'a> UniformField<'a> for $uniform_type {
+            type UniformLayout = LocationFieldLayout;
+            type UniformAccessor = UniformAccessor<'a, $uniform_type>;
+
+            fn make_uniform_layout(layout_field: LayoutInfo) -> Result<Self::UniformLayout, LayoutError> {
+                if let LayoutInfo::LocationField(location) = layout_field {
+                    Ok(LocationFieldLayout::new(location))
+                } else {
+                    Err(LayoutError)
+                }
+            }
+
+            fn make_uniform_accessor(layout: &Self::UniformLayout, sink: &'a UniformSink) -> Self::UniformAccessor {
+                UniformAccessor { location: layout.location(), sink, _phantom: PhantomData }
+            }
+        }
+
+        impl<'a> UniformArrayField<'a> for $uniform_type {
+            type UniformArrayLayout = LocationArrayFieldLayout;
+            type UniformArrayAccessor = UniformArrayAccessor<'a, $uniform_type>;
+
+            fn make_uniform_array_layout(layout_field: LayoutInfo, len: usize) -> Result<Self::UniformArrayLayout, LayoutError> {
+                if let LayoutInfo::LocationField(location) = layout_field {
+                    Ok(LocationArrayFieldLayout::new(location, len))
+                } else {
+                    Err(LayoutError)
+                }
+            }
+
+            fn make_uniform_array_accessor(layout: &Self::UniformArrayLayout, sink: &'a UniformSink) -> Self::UniformArrayAccessor {
+                UniformArrayAccessor { location: layout.location(), len: layout.len(), sink, _phantom: PhantomData }
+            }
+        }
+    )
+}
+
+macro_rules! impl_uniform_type {
+    ($uniform_type:ty : $variant:ident ($column_count:expr, $row_count:expr)) => (
+        impl UniformType for $uniform_type {
+            fn uniform_value(&self) -> UniformValue {
+                UniformValue::$variant($column_count, $row_count, components(self))
+            }
+        }
+
+        impl_uniform_field!($uniform_type);
+    );
+    ($uniform_type:ty : $variant:ident) => (
+        impl UniformType for $uniform_type {
+            fn uniform_value(&self) -> UniformValue {
+                UniformValue::$variant(components(self))
+            }
+        }
+
+        impl_uniform_field!($uniform_type);
+    );
+}
+
+impl UniformType for Bool32 {
+    fn uniform_value(&self) -> UniformValue {
+        UniformValue::Int(vec![self.get() as i32])
+    }
+}
+
+impl UniformType for TextureHandle {
+    fn uniform_value(&self) -> UniformValue {
+        UniformValue::Handle(self.raw())
+    }
+}
+
+impl UniformType for ImageHandle {
+    fn uniform_value(&self) -> UniformValue {
+        UniformValue::Handle(self.raw())
+    }
+}
+
+impl_uniform_field!(Bool32);
+impl_uniform_field!(TextureHandle);
+impl_uniform_field!(ImageHandle);
+
+impl_uniform_type!(f32: Float);
+impl_uniform_type!(Vec2: Float);
+impl_uniform_type!(Vec3: Float);
+impl_uniform_type!(Vec4: Float);
+impl_uniform_type!(f64: Double);
+impl_uniform_type!(DVec2: Double);
+impl_uniform_type!(DVec3: Double);
+impl_uniform_type!(DVec4: Double);
+impl_uniform_type!(i32: Int);
+impl_uniform_type!(IVec2: Int);
+impl_uniform_type!(IVec3: Int);
+impl_uniform_type!(IVec4: Int);
+impl_uniform_type!(u32: Uint);
+impl_uniform_type!(UVec2: Uint);
+impl_uniform_type!(UVec3: Uint);
+impl_uniform_type!(UVec4: Uint);
+impl_uniform_type!(BVec2: Int);
+impl_uniform_type!(BVec3: Int);
+impl_uniform_type!(BVec4: Int);
+
+impl_uniform_type!(Matrix2: Matrix(2, 2));
+impl_uniform_type!(Matrix2x3: Matrix(2, 3));
+impl_uniform_type!(Matrix2x4: Matrix(2, 4));
+impl_uniform_type!(Matrix3x2: Matrix(3, 2));
+impl_uniform_type!(Matrix3: Matrix(3, 3));
+impl_uniform_type!(Matrix3x4: Matrix(3, 4));
+impl_uniform_type!(Matrix4x2: Matrix(4, 2));
+impl_uniform_type!(Matrix4x3: Matrix(4, 3));
+impl_uniform_type!(Matrix4: Matrix(4, 4));
+
+impl_uniform_type!(DMatrix2: DMatrix(2, 2));
+impl_uniform_type!(DMatrix2x3: DMatrix(2, 3));
+impl_uniform_type!(DMatrix2x4: DMatrix(2, 4));
+impl_uniform_type!(DMatrix3x2: DMatrix(3, 2));
+impl_uniform_type!(DMatrix3: DMatrix(3, 3));
+impl_uniform_type!(DMatrix3x4: DMatrix(3, 4));
+impl_uniform_type!(DMatrix4x2: DMatrix(4, 2));
+impl_uniform_type!(DMatrix4x3: DMatrix(4, 3));
+impl_uniform_type!(DMatrix4: DMatrix(4, 4));
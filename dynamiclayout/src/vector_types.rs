@@ -1,5 +1,9 @@
 
 use std::ops::{Index, IndexMut};
+use Pod;
+use primitive_types::Bool32;
+use half::F16;
+use normalized::{Unorm8, Snorm8, Unorm16, Snorm16};
 
 macro_rules! make_vector_type {
     ($vector_type:ident : $field_type:ty [$field_count:expr] $($field:ident),+) => (
@@ -17,6 +21,8 @@ macro_rules! make_vector_type {
             }
         }
 
+        unsafe impl Pod for $vector_type {}
+
         impl Index<usize> for $vector_type {
             type Output = $field_type;
 
@@ -39,14 +45,106 @@ macro_rules! make_vector_type {
     )
 }
 
+// Vectors of normalized integers convert all their components to and from f32 at once
+macro_rules! impl_normalized_vector {
+    ($vector_type:ident : $field_type:ident [$field_count:expr] $($field:ident),+) => (
+        impl $vector_type {
+            pub fn get(&self) -> [f32; $field_count] {
+                [ $( $field_type::to_f32(self.$field) ),+ ]
+            }
+
+            pub fn set(&mut self, values: [f32; $field_count]) {
+                let mut values = values.iter();
+                $( self.$field = $field_type::from_f32(*values.next().unwrap()); )+
+            }
+        }
+    )
+}
+
+// The components of boolean vectors are packed, so they are read and written by value
+macro_rules! impl_bool_vector {
+    ($vector_type:ident [$field_count:expr] $($field:ident),+) => (
+        impl $vector_type {
+            pub fn get(&self, index: usize) -> bool {
+                [ $( bool::from(self.$field) ),+ ][index]
+            }
+
+            pub fn set(&mut self, index: usize, value: bool) {
+                let mut values = [ $( self.$field ),+ ];
+                values[index] = Bool32::new(value);
+                let mut values = values.iter();
+                $( self.$field = *values.next().unwrap(); )+
+            }
+        }
+    )
+}
+
 make_vector_type!(Vec2: f32 [2] x, y);
 make_vector_type!(IVec2: i32 [2] x, y);
 make_vector_type!(UVec2: u32 [2] x, y);
+make_vector_type!(DVec2: f64 [2] x, y);
+make_vector_type!(BVec2: Bool32 [2] x, y);
+impl_bool_vector!(BVec2 [2] x, y);
+make_vector_type!(F16Vec2: F16 [2] x, y);
+make_vector_type!(I16Vec2: i16 [2] x, y);
+make_vector_type!(U16Vec2: u16 [2] x, y);
+make_vector_type!(I8Vec2: i8 [2] x, y);
+make_vector_type!(U8Vec2: u8 [2] x, y);
+make_vector_type!(I64Vec2: i64 [2] x, y);
+make_vector_type!(U64Vec2: u64 [2] x, y);
 
 make_vector_type!(Vec3: f32 [3] x, y, z);
 make_vector_type!(IVec3: i32 [3] x, y, z);
 make_vector_type!(UVec3: u32 [3] x, y, z);
+make_vector_type!(DVec3: f64 [3] x, y, z);
+make_vector_type!(BVec3: Bool32 [3] x, y, z);
+impl_bool_vector!(BVec3 [3] x, y, z);
+make_vector_type!(F16Vec3: F16 [3] x, y, z);
+make_vector_type!(I16Vec3: i16 [3] x, y, z);
+make_vector_type!(U16Vec3: u16 [3] x, y, z);
+make_vector_type!(I8Vec3: i8 [3] x, y, z);
+make_vector_type!(U8Vec3: u8 [3] x, y, z);
+make_vector_type!(I64Vec3: i64 [3] x, y, z);
+make_vector_type!(U64Vec3: u64 [3] x, y, z);
 
 make_vector_type!(Vec4: f32 [4] x, y, z, w);
 make_vector_type!(IVec4: i32 [4] x, y, z, w);
 make_vector_type!(UVec4: u32 [4] x, y, z, w);
+make_vector_type!(DVec4: f64 [4] x, y, z, w);
+make_vector_type!(BVec4: Bool32 [4] x, y, z, w);
+impl_bool_vector!(BVec4 [4] x, y, z, w);
+make_vector_type!(F16Vec4: F16 [4] x, y, z, w);
+make_vector_type!(I16Vec4: i16 [4] x, y, z, w);
+make_vector_type!(U16Vec4: u16 [4] x, y, z, w);
+make_vector_type!(I8Vec4: i8 [4] x, y, z, w);
+make_vector_type!(U8Vec4: u8 [4] x, y, z, w);
+make_vector_type!(I64Vec4: i64 [4] x, y, z, w);
+make_vector_type!(U64Vec4: u64 [4] x, y, z, w);
+
+make_vector_type!(Unorm8Vec2: Unorm8 [2] x, y);
+impl_normalized_vector!(Unorm8Vec2: Unorm8 [2] x, y);
+make_vector_type!(Unorm8Vec3: Unorm8 [3] x, y, z);
+impl_normalized_vector!(Unorm8Vec3: Unorm8 [3] x, y, z);
+make_vector_type!(Unorm8Vec4: Unorm8 [4] x, y, z, w);
+impl_normalized_vector!(Unorm8Vec4: Unorm8 [4] x, y, z, w);
+
+make_vector_type!(Snorm8Vec2: Snorm8 [2] x, y);
+impl_normalized_vector!(Snorm8Vec2: Snorm8 [2] x, y);
+make_vector_type!(Snorm8Vec3: Snorm8 [3] x, y, z);
+impl_normalized_vector!(Snorm8Vec3: Snorm8 [3] x, y, z);
+make_vector_type!(Snorm8Vec4: Snorm8 [4] x, y, z, w);
+impl_normalized_vector!(Snorm8Vec4: Snorm8 [4] x, y, z, w);
+
+make_vector_type!(Unorm16Vec2: Unorm16 [2] x, y);
+impl_normalized_vector!(Unorm16Vec2: Unorm16 [2] x, y);
+make_vector_type!(Unorm16Vec3: Unorm16 [3] x, y, z);
+impl_normalized_vector!(Unorm16Vec3: Unorm16 [3] x, y, z);
+make_vector_type!(Unorm16Vec4: Unorm16 [4] x, y, z, w);
+impl_normalized_vector!(Unorm16Vec4: Unorm16 [4] x, y, z, w);
+
+make_vector_type!(Snorm16Vec2: Snorm16 [2] x, y);
+impl_normalized_vector!(Snorm16Vec2: Snorm16 [2] x, y);
+make_vector_type!(Snorm16Vec3: Snorm16 [3] x, y, z);
+impl_normalized_vector!(Snorm16Vec3: Snorm16 [3] x, y, z);
+make_vector_type!(Snorm16Vec4: Snorm16 [4] x, y, z, w);
+impl_normalized_vector!(Snorm16Vec4: Snorm16 [4] x, y, z, w);
@@ -0,0 +1,272 @@
+use {Data, DynamicLayout, AccessorError, LayoutError, OffsetType, StrideType};
+use load::LayoutInfo;
+use primitive_types::Bool32;
+use half::F16;
+use normalized::*;
+use vector_types::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ComponentType {
+    Float16,
+    Float32,
+    Float64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Unorm8,
+    Snorm8,
+    Unorm16,
+    Snorm16,
+}
+
+impl ComponentType {
+    pub fn size(&self) -> usize {
+        match *self {
+            ComponentType::Int8 | ComponentType::Uint8 | ComponentType::Unorm8 | ComponentType::Snorm8 => 1,
+            ComponentType::Float16 | ComponentType::Int16 | ComponentType::Uint16
+                | ComponentType::Unorm16 | ComponentType::Snorm16 => 2,
+            ComponentType::Float32 | ComponentType::Int32 | ComponentType::Uint32 => 4,
+            ComponentType::Float64 | ComponentType::Int64 | ComponentType::Uint64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VertexFormat {
+    // Component type and count
+    Vector(ComponentType, u8),
+    // Formats packed into one u32
+    R10G10B10A2,
+    R11G11B10F,
+}
+
+impl VertexFormat {
+    pub fn size(&self) -> usize {
+        match *self {
+            VertexFormat::Vector(component, count) => component.size() * count as usize,
+            VertexFormat::R10G10B10A2 | VertexFormat::R11G11B10F => 4,
+        }
+    }
+}
+
+/// Field types that can be read by the vertex input stage.
+pub trait VertexAttributeType {
+    fn vertex_format() -> VertexFormat;
+}
+
+/// Structs whose fields are all vertex attributes, usually implemented with `#[derive(VertexLayout)]`
+/// next to `#[derive(DynamicLayout)]`. The offsets and the stride are those of the `repr(C)` struct.
+pub trait VertexLayout {
+    // Name, offset and format of every field
+    fn attributes() -> Vec<(&'static str, usize, VertexFormat)>;
+
+    fn stride() -> usize;
+}
+
+macro_rules! impl_vertex_attribute_type {
+    ($field_type:ty : $component_type:ident [$count:expr]) => (
+        impl_vertex_attribute_type!($field_type : VertexFormat::Vector(ComponentType::$component_type, $count));
+    );
+    ($field_type:ty : $format:expr) => (
+        impl VertexAttributeType for $field_type {
+            fn vertex_format() -> VertexFormat {
+                $format
+            }
+        }
+    );
+}
+
+impl_vertex_attribute_type!(f32: Float32 [1]);
+impl_vertex_attribute_type!(Vec2: Float32 [2]);
+impl_vertex_attribute_type!(Vec3: Float32 [3]);
+impl_vertex_attribute_type!(Vec4: Float32 [4]);
+impl_vertex_attribute_type!(f64: Float64 [1]);
+impl_vertex_attribute_type!(DVec2: Float64 [2]);
+impl_vertex_attribute_type!(DVec3: Float64 [3]);
+impl_vertex_attribute_type!(DVec4: Float64 [4]);
+impl_vertex_attribute_type!(F16: Float16 [1]);
+impl_vertex_attribute_type!(F16Vec2: Float16 [2]);
+impl_vertex_attribute_type!(F16Vec3: Float16 [3]);
+impl_vertex_attribute_type!(F16Vec4: Float16 [4]);
+impl_vertex_attribute_type!(i32: Int32 [1]);
+impl_vertex_attribute_type!(IVec2: Int32 [2]);
+impl_vertex_attribute_type!(IVec3: Int32 [3]);
+impl_vertex_attribute_type!(IVec4: Int32 [4]);
+impl_vertex_attribute_type!(u32: Uint32 [1]);
+impl_vertex_attribute_type!(UVec2: Uint32 [2]);
+impl_vertex_attribute_type!(UVec3: Uint32 [3]);
+impl_vertex_attribute_type!(UVec4: Uint32 [4]);
+// Booleans are read as unsigned integers
+impl_vertex_attribute_type!(Bool32: Uint32 [1]);
+impl_vertex_attribute_type!(i16: Int16 [1]);
+impl_vertex_attribute_type!(I16Vec2: Int16 [2]);
+impl_vertex_attribute_type!(I16Vec3: Int16 [3]);
+impl_vertex_attribute_type!(I16Vec4: Int16 [4]);
+impl_vertex_attribute_type!(u16: Uint16 [1]);
+impl_vertex_attribute_type!(U16Vec2: Uint16 [2]);
+impl_vertex_attribute_type!(U16Vec3: Uint16 [3]);
+impl_vertex_attribute_type!(U16Vec4: Uint16 [4]);
+impl_vertex_attribute_type!(i8: Int8 [1]);
+impl_vertex_attribute_type!(I8Vec2: Int8 [2]);
+impl_vertex_attribute_type!(I8Vec3: Int8 [3]);
+impl_vertex_attribute_type!(I8Vec4: Int8 [4]);
+impl_vertex_attribute_type!(u8: Uint8 [1]);
+impl_vertex_attribute_type!(U8Vec2: Uint8 [2]);
+impl_vertex_attribute_type!(U8Vec3: Uint8 [3]);
+impl_vertex_attribute_type!(U8Vec4: Uint8 [4]);
+impl_vertex_attribute_type!(i64: Int64 [1]);
+impl_vertex_attribute_type!(I64Vec2: Int64 [2]);
+impl_vertex_attribute_type!(I64Vec3: Int64 [3]);
+impl_vertex_attribute_type!(I64Vec4: Int64 [4]);
+impl_vertex_attribute_type!(u64: Uint64 [1]);
+impl_vertex_attribute_type!(U64Vec2: Uint64 [2]);
+impl_vertex_attribute_type!(U64Vec3: Uint64 [3]);
+impl_vertex_attribute_type!(U64Vec4: Uint64 [4]);
+impl_vertex_attribute_type!(Unorm8: Unorm8 [1]);
+impl_vertex_attribute_type!(Unorm8Vec2: Unorm8 [2]);
+impl_vertex_attribute_type!(Unorm8Vec3: Unorm8 [3]);
+impl_vertex_attribute_type!(Unorm8Vec4: Unorm8 [4]);
+impl_vertex_attribute_type!(Snorm8: Snorm8 [1]);
+impl_vertex_attribute_type!(Snorm8Vec2: Snorm8 [2]);
+impl_vertex_attribute_type!(Snorm8Vec3: Snorm8 [3]);
+impl_vertex_attribute_type!(Snorm8Vec4: Snorm8 [4]);
+impl_vertex_attribute_type!(Unorm16: Unorm16 [1]);
+impl_vertex_attribute_type!(Unorm16Vec2: Unorm16 [2]);
+impl_vertex_attribute_type!(Unorm16Vec3: Unorm16 [3]);
+impl_vertex_attribute_type!(Unorm16Vec4: Unorm16 [4]);
+impl_vertex_attribute_type!(Snorm16: Snorm16 [1]);
+impl_vertex_attribute_type!(Snorm16Vec2: Snorm16 [2]);
+impl_vertex_attribute_type!(Snorm16Vec3: Snorm16 [3]);
+impl_vertex_attribute_type!(Snorm16Vec4: Snorm16 [4]);
+impl_vertex_attribute_type!(R10G10B10A2: VertexFormat::R10G10B10A2);
+impl_vertex_attribute_type!(R11G11B10F: VertexFormat::R11G11B10F);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InputRate {
+    Vertex,
+    Instance,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub name: &'static str,
+    pub offset: OffsetType,
+    pub format: VertexFormat,
+}
+
+/// Layout of one interleaved vertex or instance buffer binding: the layout of a single element,
+/// the stride between elements and the attribute descriptions for the graphics API.
+pub struct VertexBufferLayout<L> {
+    layout: L,
+    element_len: usize,
+    stride: StrideType,
+    input_rate: InputRate,
+    attributes: Vec<VertexAttribute>,
+}
+
+impl<L> VertexBufferLayout<L> {
+    /// Layout of a buffer of `T` itself, with the offsets and the size of the struct.
+    pub fn new<'a, T>(input_rate: InputRate) -> Result<VertexBufferLayout<L>, LayoutError>
+            where T: DynamicLayout<'a, Layout = L> + VertexLayout {
+        let mut fields = Vec::new();
+        for (name, offset, _) in T::attributes() {
+            if offset > OffsetType::max_value() as usize {
+                return Err(LayoutError);
+            }
+            fields.push((name, LayoutInfo::PrimitiveField(offset as OffsetType)));
+        }
+        if T::stride() > StrideType::max_value() as usize {
+            return Err(LayoutError);
+        }
+        let layout = T::load_layout(&&fields[..])?;
+        VertexBufferLayout::from_layout::<T>(layout, T::stride() as StrideType, input_rate)
+    }
+
+    /// For elements laid out differently from `T`, e.g. with the offsets from shader reflection. The element
+    /// layout is loaded like that of any block, with every attribute as a `PrimitiveField`.
+    /// Fails if an element does not fit into the stride, or the stride would misalign the elements after the first.
+    pub fn from_layout<'a, T>(layout: L, stride: StrideType, input_rate: InputRate) -> Result<VertexBufferLayout<L>, LayoutError>
+            where T: DynamicLayout<'a, Layout = L> + VertexLayout {
+        // Every element has to be aligned like the first one
+        let element_len = T::required_data_len(&layout);
+        if element_len > stride as usize || stride as usize % T::required_alignment(&layout) != 0 {
+            return Err(LayoutError);
+        }
+        let spans: Vec<_> = T::get_named_field_spans(&layout).collect();
+        let mut attributes = Vec::new();
+        for (name, _, format) in T::attributes() {
+            let span = spans.iter().find(|&&(ref path, _)| path == name).map(|&(_, span)| span).ok_or(LayoutError)?;
+            attributes.push(VertexAttribute { name, offset: span.offset, format });
+        }
+        Ok(VertexBufferLayout { layout, element_len, stride, input_rate, attributes })
+    }
+
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    pub fn stride(&self) -> StrideType {
+        self.stride
+    }
+
+    pub fn input_rate(&self) -> InputRate {
+        self.input_rate
+    }
+
+    pub fn attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&VertexAttribute> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+
+    // The last element does not need the padding up to the full stride
+    pub fn element_count(&self, data_len: usize) -> usize {
+        if self.stride == 0 || data_len < self.element_len {
+            0
+        } else {
+            (data_len - self.element_len) / self.stride as usize + 1
+        }
+    }
+}
+
+/// Interleaved vertex or instance data, accessed one element at a time through a `VertexBufferLayout`.
+pub struct VertexBuffer<'a> {
+    data: Data<'a>,
+}
+
+impl<'a> VertexBuffer<'a> {
+    pub fn new(data: Data<'a>) -> VertexBuffer<'a> {
+        VertexBuffer { data }
+    }
+
+    pub fn len<L>(&self, layout: &VertexBufferLayout<L>) -> usize {
+        layout.element_count(self.data.len())
+    }
+
+    /// Accessor for element `index`, an error if the data ends before that element does.
+    pub fn element<'b, T>(&'b mut self, layout: &VertexBufferLayout<T::Layout>, index: usize) -> Result<T::Accessor, AccessorError>
+            where T: DynamicLayout<'b> {
+        let data_len = self.data.len();
+        let offset = match (layout.stride() as usize).checked_mul(index) {
+            Some(offset) => offset,
+            None => return Err(AccessorError {
+                required_data_len: usize::max_value(),
+                data_len,
+                required_alignment: T::required_alignment(layout.layout()),
+            }),
+        };
+        let element = self.data.reborrow().sub(::std::cmp::min(offset, data_len)..data_len).unwrap();
+        T::make_accessor(layout.layout(), element).map_err(|e| AccessorError {
+            required_data_len: offset.saturating_add(e.required_data_len),
+            data_len,
+            required_alignment: e.required_alignment,
+        })
+    }
+}
@@ -9,9 +9,22 @@ extern crate dynamiclayout_derive;
 use dynamiclayout::{DynamicLayout, Field, ArrayField, Data};
 use dynamiclayout::helper::UnsafeArrayHelper;
 use dynamiclayout::load::{LayoutInfo, FieldSpan};
+use dynamiclayout::load::MatrixOrder;
 use dynamiclayout::load::LayoutInfo::*;
-use dynamiclayout::vector_types::{Vec2, Vec3, Vec4};
-use dynamiclayout::matrix_types::{Matrix4, Matrix2x3};
+use dynamiclayout::vector_types::{Vec2, Vec3, Vec4, DVec3, BVec3, F16Vec2, U8Vec4, U64Vec2, Unorm8Vec4, Snorm16Vec3};
+use dynamiclayout::half::F16;
+use dynamiclayout::vertex::{VertexBufferLayout, VertexBuffer, VertexAttribute, VertexFormat, ComponentType, InputRate};
+use dynamiclayout::specialization::{SpecializationConstants, SpecializationMapEntry};
+use dynamiclayout::push_constants::{PushConstants, PushConstantRange, PushConstantError, ShaderStages};
+use dynamiclayout::uniforms::{UniformSink, UniformValue, load_uniform_layout};
+use dynamiclayout::program::{ProgramLayout, BlockDescriptor, BlockKind};
+use dynamiclayout::normalized::{Unorm8, Snorm8, Unorm16, Snorm16, R10G10B10A2, R11G11B10F};
+use dynamiclayout::handles::{TextureHandle, ImageHandle, DevicePointer};
+use dynamiclayout::enums::InvalidDiscriminant;
+use dynamiclayout::primitive_types::Bool32;
+use dynamiclayout::matrix_types::{Matrix4, Matrix2x3, DMatrix2, RowMajor};
+use dynamiclayout::runtime_array::RuntimeArray;
+use dynamiclayout::dynamic_array::DynamicArray;
 
 
 #[test]
@@ -372,3 +385,1146 @@ fn struct_array() {
     assert_eq!(acc.array[1].four.z, 12.0);
     assert_eq!(acc.array[1].four.w, 13.0);
 }
+
+#[test]
+fn dynamic_buffer_allocator() {
+    use dynamiclayout::allocator::DynamicBufferAllocator;
+
+    let layout = make_foo_layout();
+    let mut buffer = [0u32; 256];
+    let data = unsafe { Data::from_anything(&mut buffer) };
+    let mut allocator = DynamicBufferAllocator::new(data, 256);
+
+    let offsets: Vec<_> = (0..4).map(|_| allocator.allocate::<Foo>(&layout).unwrap().offset).collect();
+    assert_eq!(offsets, vec![0, 256, 512, 768]);
+    assert!(allocator.allocate::<Foo>(&layout).is_err());
+    allocator.finish_frame();
+
+    // The buffer is full until the GPU is done with the first frame
+    assert!(allocator.allocate::<Foo>(&layout).is_err());
+    assert!(allocator.release_frame());
+    {
+        // Allocations of one frame can be written at the same time
+        let first = allocator.allocate::<Foo>(&layout).unwrap();
+        let second = allocator.allocate::<Foo>(&layout).unwrap();
+        assert_eq!((first.offset, second.offset), (0, 256));
+        *first.accessor.one = 4.0;
+        *second.accessor.one = 5.0;
+    }
+    assert_eq!(buffer[3], 4.0f32.to_bits());
+    assert_eq!(buffer[64 + 3], 5.0f32.to_bits());
+}
+
+#[test]
+fn dynamic_buffer_allocator_wrap_to_tail() {
+    use dynamiclayout::allocator::DynamicBufferAllocator;
+
+    let layout = make_foo_layout();
+    let mut buffer = [0u32; 256];
+    let data = unsafe { Data::from_anything(&mut buffer) };
+    let mut allocator = DynamicBufferAllocator::new(data, 256);
+
+    // A frame that wraps and ends exactly at the tail frees the whole buffer when it is released
+    for _ in 0..3 {
+        allocator.allocate::<Foo>(&layout).unwrap();
+    }
+    allocator.finish_frame();
+    assert!(allocator.release_frame());
+    let offsets: Vec<_> = (0..4).map(|_| allocator.allocate::<Foo>(&layout).unwrap().offset).collect();
+    assert_eq!(offsets, vec![768, 0, 256, 512]);
+    assert!(allocator.allocate::<Foo>(&layout).is_err());
+    allocator.finish_frame();
+    assert!(allocator.release_frame());
+    let offsets: Vec<_> = (0..2).map(|_| allocator.allocate::<Foo>(&layout).unwrap().offset).collect();
+    assert_eq!(offsets, vec![768, 0]);
+}
+
+#[test]
+fn aligned_buffer() {
+    use dynamiclayout::buffer::{AlignedBuffer, BUFFER_ALIGNMENT};
+
+    let layout = make_foo_layout();
+    let mut buffer = AlignedBuffer::for_layout::<Foo>(&layout);
+    assert_eq!(buffer.len(), FOO_SIZE);
+    assert_eq!(buffer.as_slice().as_ptr() as usize % BUFFER_ALIGNMENT, 0);
+    {
+        let mut data = buffer.data();
+        let acc = layout.make_accessor(&mut data).unwrap();
+        *acc.one = 1.0;
+    }
+    assert_eq!(&buffer.as_slice()[12..16], &1.0f32.to_bits().to_ne_bytes()[..]);
+
+    buffer.resize(8);
+    buffer.resize(FOO_SIZE);
+    assert!(buffer.as_slice()[8..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn pod_data() {
+    let layout = make_primitive_array_layout();
+    let mut pa = PrimitiveArray {
+        first: 11,
+        array: [1, 2, 3, 4, 5, 6, 7, 8],
+        last: 99,
+    };
+    {
+        let mut data = Data::from_pod(&mut pa).unwrap();
+        let acc = layout.make_accessor(&mut data).unwrap();
+        *acc.last = 100;
+    }
+    assert_eq!(pa.last, 100);
+
+    let mut values = [0u32; 10];
+    assert_eq!(Data::from_pod_slice(&mut values[..]).unwrap().len(), 40);
+}
+
+#[test]
+fn dirty_ranges_coalesce() {
+    use dynamiclayout::tracking::DirtyRanges;
+
+    let mut ranges = DirtyRanges::new();
+    ranges.mark(FieldSpan::new(16, 4));
+    ranges.mark(FieldSpan::new(0, 4));
+    ranges.mark(FieldSpan::new(8, 4));
+    ranges.mark(FieldSpan::new(4, 4));
+    ranges.mark(FieldSpan::new(18, 8));
+    let expected: &[FieldSpan] = &[FieldSpan::new(0, 12), FieldSpan::new(16, 10)];
+    assert_eq!(ranges.spans(), expected);
+}
+
+#[test]
+fn data_sub() {
+    let layout = make_primitive_array_layout();
+    let mut values = [0u32; 12];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        assert!(data.reborrow().sub(8..52).is_none());
+        let mut acc = <PrimitiveArray as DynamicLayout>::make_accessor(&layout, data.reborrow().sub(8..48).unwrap()).unwrap();
+        *acc.first = 1;
+        acc.array[7] = 2;
+    }
+    assert_eq!(values[2], 1);
+    assert_eq!(values[10], 2);
+}
+
+#[test]
+fn tracked_accessor() {
+    use dynamiclayout::tracking::DirtyTracker;
+
+    let layout = make_primitive_array_layout();
+    let mut pa = PrimitiveArray {
+        first: 11,
+        array: [1, 2, 3, 4, 5, 6, 7, 8],
+        last: 99,
+    };
+    let tracker = DirtyTracker::new();
+    {
+        let mut data = Data::from_pod(&mut pa).unwrap();
+        let mut acc = layout.make_tracked_accessor(&mut data, &tracker).unwrap();
+        assert_eq!(*acc.first, 11);
+        assert_eq!(acc.array[7], 8);
+        assert!(!tracker.is_dirty());
+        acc.first.set(12);
+        acc.array.set(0, 10);
+        acc.array.set(7, 80);
+    }
+    let expected: &[FieldSpan] = &[FieldSpan::new(0, 8), FieldSpan::new(32, 4)];
+    assert_eq!(tracker.take().spans(), expected);
+    assert!(!tracker.is_dirty());
+    assert_eq!(pa.array[7], 80);
+}
+
+#[test]
+#[should_panic(expected = "does not fit into OffsetType")]
+fn tracked_array_offset_overflow() {
+    use dynamiclayout::accessor::PrimitiveArrayAccessor;
+    use dynamiclayout::tracking::{DirtyTracker, TrackedArrayAccessor};
+
+    let mut values = [0u32; 2];
+    let tracker = DirtyTracker::new();
+    let inner = unsafe { PrimitiveArrayAccessor::new(values.as_mut_ptr() as *mut u8, 4, 2) };
+    let mut acc = TrackedArrayAccessor::new(inner, 65532, 4, &tracker);
+    acc.set(0, 1);
+    assert_eq!(tracker.take().spans(), &[FieldSpan::new(65532, 4)][..]);
+    // The second element starts past the last offset a FieldSpan can describe
+    acc.set(1, 2);
+}
+
+#[test]
+fn shadow_block_changes() {
+    use dynamiclayout::shadow::ShadowBlock;
+
+    let layout = make_foo_layout();
+    let mut block = ShadowBlock::new::<Foo>(&layout);
+    assert_eq!(block.take_changes().len(), 10);
+    assert!(block.take_changes().is_empty());
+    {
+        let mut acc = block.accessor::<Foo>(&layout).unwrap();
+        acc.one.set(0.0);
+        acc.compound.one.set(1.0);
+        acc.compound.matrix[2].set([0.0, 2.0, 0.0, 0.0]);
+    }
+    let changes = block.take_changes();
+    let paths: Vec<_> = changes.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(paths, vec!["compound.one", "compound.matrix[2]"]);
+    assert_eq!(changes[1].span, FieldSpan::new(92, 16));
+    assert_eq!(&block.as_slice()[40..44], &1.0f32.to_bits().to_ne_bytes()[..]);
+    {
+        // Writing the same values again changes nothing
+        let mut acc = block.accessor::<Foo>(&layout).unwrap();
+        acc.compound.one.set(1.0);
+        acc.compound.matrix[2].set([0.0, 2.0, 0.0, 0.0]);
+    }
+    assert!(block.take_changes().is_empty());
+}
+
+#[test]
+fn primitive_array_slice_api() {
+    let layout = make_primitive_array_layout();
+    let mut pa = PrimitiveArray {
+        first: 11,
+        array: [1, 2, 3, 4, 5, 6, 7, 8],
+        last: 99,
+    };
+    {
+        let mut data = Data::from_pod(&mut pa).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+
+        assert_eq!(acc.array.len(), 8);
+        assert_eq!(acc.array.get(7), Some(&8));
+        assert_eq!(acc.array.get(8), None);
+        assert_eq!(acc.array.iter().rev().next(), Some(&8));
+        assert_eq!(acc.array.iter().cloned().sum::<i32>(), 36);
+
+        acc.array.fill(0);
+        for (i, value) in acc.array.iter_mut().enumerate().skip(4) {
+            *value = i as i32;
+        }
+        assert_eq!(acc.array.iter().cloned().collect::<Vec<_>>(), vec![0, 0, 0, 0, 4, 5, 6, 7]);
+        acc.array.copy_from_slice(&[9, 9, 9, 9, 9, 9, 9, 9]);
+        *acc.array.get_mut(0).unwrap() = 1;
+    }
+    assert_eq!(pa.array, [1, 9, 9, 9, 9, 9, 9, 9]);
+    assert_eq!(pa.last, 99);
+}
+
+#[test]
+#[should_panic(expected = "does not match accessor length")]
+fn primitive_array_copy_from_short_slice() {
+    let layout = make_primitive_array_layout();
+    let mut values = [0i32; 10];
+    let mut data = Data::from_pod(&mut values).unwrap();
+    let mut acc = layout.make_accessor(&mut data).unwrap();
+    acc.array.copy_from_slice(&[1, 2, 3]);
+}
+
+#[test]
+fn parallel_array_fill() {
+    let layout = make_primitive_array_layout();
+    let mut values = [0i32; 10];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        {
+            let (mut low, mut high) = acc.array.split_at_mut(4);
+            assert_eq!((low.len(), high.len()), (4, 4));
+            low[3] = -1;
+            high[0] = -2;
+        }
+        assert_eq!(acc.array.chunks_mut(3).map(|c| c.len()).collect::<Vec<_>>(), vec![3, 3, 2]);
+        ::std::thread::scope(|scope| {
+            for (i, mut chunk) in acc.array.chunks_mut(2).enumerate() {
+                scope.spawn(move || chunk.fill(i as i32));
+            }
+        });
+    }
+    assert_eq!(values, [0, 0, 0, 1, 1, 2, 2, 3, 3, 0]);
+
+    let layout = make_struct_array_layout();
+    let mut sa = [0f32; 10];
+    {
+        let mut data = Data::from_pod(&mut sa).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        ::std::thread::scope(|scope| {
+            for (i, element) in acc.array.iter_mut().enumerate() {
+                scope.spawn(move || *element.one = i as f32 + 1.0);
+            }
+        });
+    }
+    assert_eq!(sa[0], 1.0);
+    assert_eq!(sa[5], 2.0);
+}
+
+#[test]
+fn struct_array_columns() {
+    let layout = make_struct_array_layout();
+    let mut sa = [0f32; 10];
+    {
+        let mut data = Data::from_pod(&mut sa).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+
+        let mut ones = acc.array.column_one().unwrap();
+        assert_eq!(ones.len(), 2);
+        assert_eq!(ones.stride(), 20);
+        ones.copy_from_slice(&[1.0, 2.0]);
+
+        let mut fours = acc.array.column_four().unwrap();
+        fours.fill(Vec4::new(5.0, 6.0, 7.0, 8.0));
+        fours[1].w = 9.0;
+
+        assert_eq!(*acc.array[1].one, 2.0);
+    }
+    assert_eq!(sa, [1.0, 5.0, 6.0, 7.0, 8.0, 2.0, 5.0, 6.0, 7.0, 9.0]);
+}
+
+#[test]
+fn reversed_struct_array_column() {
+    // The elements are stored in reverse order, which can't be described with a positive stride
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("array",
+                                       StructArrayField(&[&QUX_LAYOUT_1,
+                                                          &QUX_LAYOUT_0]))];
+    let layout = StructArray::load_layout(&FIELDS).unwrap();
+    let mut values = [0f32; 10];
+    let mut data = Data::from_pod(&mut values).unwrap();
+    let mut acc = layout.make_accessor(&mut data).unwrap();
+    assert!(acc.array.column_one().is_none());
+    assert!(acc.array.column_four().is_none());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct ParticleBuffer {
+    pub count: u32,
+    pub particles: RuntimeArray<Qux>,
+}
+
+const PARTICLE_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("one", PrimitiveField(16)),
+                                                                          ("four", PrimitiveField(32))];
+const PARTICLE_LAYOUT: LayoutInfo<'static> = StructField(&PARTICLE_FIELDS);
+const P_B_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("count", PrimitiveField(0)),
+                                                                     ("particles",
+                                                                      RuntimeArrayField(&PARTICLE_LAYOUT, 32))];
+
+#[test]
+fn runtime_sized_array() {
+    let layout = ParticleBuffer::load_layout(&P_B_FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 4);
+
+    let mut values = [0f32; 64];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        // The last element ends at byte 240, the one after that would not fit in 256 bytes
+        assert_eq!(acc.particles.len(), 7);
+        *acc.particles[6].one = 6.0;
+        acc.particles[1].four.w = 1.0;
+    }
+    assert_eq!(values[4 + 6 * 8], 6.0);
+    assert_eq!(values[8 + 8 + 3], 1.0);
+
+    let mut short = [0f32; 10];
+    let mut data = Data::from_pod(&mut short).unwrap();
+    assert_eq!(layout.make_accessor(&mut data).unwrap().particles.len(), 0);
+
+    // The first element must not overlap the count
+    const OVERLAPPING: &'static [(&'static str, LayoutInfo<'static>)] = &[("count", PrimitiveField(16)),
+                                                                          ("particles",
+                                                                           RuntimeArrayField(&PARTICLE_LAYOUT, 32))];
+    assert!(ParticleBuffer::load_layout(&OVERLAPPING).is_err());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct MisplacedRuntimeArray {
+    pub particles: RuntimeArray<Qux>,
+    pub count: u32,
+}
+
+#[test]
+fn runtime_sized_array_not_last() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("particles", RuntimeArrayField(&PARTICLE_LAYOUT, 32)),
+                                                                     ("count", PrimitiveField(0))];
+    assert!(MisplacedRuntimeArray::load_layout(&FIELDS).is_err());
+}
+
+#[test]
+fn tracked_runtime_sized_array() {
+    use dynamiclayout::tracking::DirtyTracker;
+
+    let layout = ParticleBuffer::load_layout(&P_B_FIELDS).unwrap();
+    let mut values = [0f32; 64];
+    let tracker = DirtyTracker::new();
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_tracked_accessor(&mut data, &tracker).unwrap();
+        acc.particles[2].one.set(2.0);
+        acc.particles[3].four.get_mut().x = 3.0;
+    }
+    let expected: &[FieldSpan] = &[FieldSpan::new(80, 4), FieldSpan::new(128, 16)];
+    assert_eq!(tracker.take().spans(), expected);
+
+    // Offsets come from the layout, so the tracker can be used with other data anywhere in memory
+    let mut other = [0f32; 16];
+    {
+        let mut data = Data::from_pod(&mut other).unwrap();
+        layout.make_tracked_accessor(&mut data, &tracker).unwrap().count.set(1);
+    }
+    assert_eq!(tracker.take().spans(), &[FieldSpan::new(0, 4)][..]);
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct SpecializedArrays {
+    pub weights: DynamicArray<f32>,
+    pub lights: DynamicArray<Qux>,
+}
+
+#[test]
+fn load_time_sized_arrays() {
+    const QUX_FIELDS_FIRST: &'static [(&'static str, LayoutInfo<'static>)] = &[("one", PrimitiveField(48)),
+                                                                              ("four", PrimitiveField(52))];
+    const QUX_LAYOUT_FIRST: LayoutInfo<'static> = StructField(&QUX_FIELDS_FIRST);
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("weights",
+                                                                      DynamicArrayField(&PrimitiveField(0), 16, 3)),
+                                                                     ("lights",
+                                                                      DynamicArrayField(&QUX_LAYOUT_FIRST, 20, 2))];
+    let layout = SpecializedArrays::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 88);
+
+    let mut values = [0f32; 22];
+    {
+        let mut data = Data::from_pod_slice(&mut values[..]).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        assert_eq!(acc.weights.len(), 3);
+        assert_eq!(acc.lights.len(), 2);
+        *acc.weights[2] = 2.0;
+        *acc.lights[1].one = 1.0;
+    }
+    assert_eq!(values[8], 2.0);
+    assert_eq!(values[17], 1.0);
+
+    // Struct arrays can also be given a layout per element
+    const PER_ELEMENT: &'static [(&'static str, LayoutInfo<'static>)] = &[("weights",
+                                                                           DynamicArrayField(&PrimitiveField(40), 4, 1)),
+                                                                          ("lights",
+                                                                           StructArrayField(&[&QUX_LAYOUT_0,
+                                                                                              &QUX_LAYOUT_1]))];
+    let layout = SpecializedArrays::load_layout(&PER_ELEMENT).unwrap();
+    assert_eq!(layout.required_data_len(), 44);
+
+    // The last weight would start past the largest offset
+    assert!(<DynamicArray<f32> as Field>::make_layout(DynamicArrayField(&PrimitiveField(0), 16, 5000)).is_err());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct RowMajorMatrices {
+    pub single: RowMajor<Matrix2x3>,
+    pub array: [RowMajor<Matrix2x3>; 2],
+}
+
+const R_M_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("single", MatrixField(0, 16, MatrixOrder::RowMajor)),
+                                                                     ("array", OrderedMatrixArrayField(48, 48, 16, MatrixOrder::RowMajor))];
+
+#[test]
+fn row_major_matrices() {
+    use dynamiclayout::tracking::DirtyTracker;
+
+    let layout = RowMajorMatrices::load_layout(&R_M_FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 136);
+
+    let mut values = [0f32; 64];
+    values[4] = 3.0;
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        assert_eq!(acc.single[0][1], 3.0);
+        assert_eq!(acc.single[1].len(), 3);
+        acc.single[1][2] = 5.0;
+        acc.array[1][0][1] = 7.0;
+    }
+    assert_eq!(values[9], 5.0);
+    assert_eq!(values[28], 7.0);
+
+    let spans: Vec<_> = <RowMajorMatrices as DynamicLayout>::get_named_field_spans(&layout).collect();
+    assert_eq!(spans.len(), 6 + 12);
+    assert!(spans.contains(&("single[1][2]".to_string(), FieldSpan::new(36, 4))));
+    assert!(spans.contains(&("array[1][0][1]".to_string(), FieldSpan::new(112, 4))));
+
+    let tracker = DirtyTracker::new();
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_tracked_accessor(&mut data, &tracker).unwrap();
+        acc.single[0].set(2, 1.0);
+    }
+    assert_eq!(tracker.take().spans(), &[FieldSpan::new(32, 4)][..]);
+    assert_eq!(values[8], 1.0);
+
+    // Plain matrix types stay column-major
+    const ROW_MAJOR_ARRAY: &'static [(&'static str, LayoutInfo<'static>)] = &[("array", OrderedMatrixArrayField(0, 12, 24, MatrixOrder::RowMajor))];
+    assert!(MatrixArray::load_layout(&ROW_MAJOR_ARRAY).is_err());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct NestedArrays {
+    pub weights: [[f32; 3]; 2],
+    pub cascades: [[Matrix2x3; 2]; 2],
+    pub grid: [[[u32; 2]; 2]; 2],
+}
+
+const N_A_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("weights",
+                                                                      NestedArrayField(&ArrayField(0, 4), 16)),
+                                                                     ("cascades",
+                                                                      NestedArrayField(&MatrixArrayField(32, 24, 12), 48)),
+                                                                     ("grid",
+                                                                      NestedArrayField(&NestedArrayField(&ArrayField(128, 4), 8), 16))];
+
+#[test]
+fn nested_arrays() {
+    let layout = NestedArrays::load_layout(&N_A_FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 160);
+
+    let mut values = [0f32; 64];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        assert_eq!(acc.weights.len(), 2);
+        assert_eq!(acc.weights[1].len(), 3);
+        acc.weights[1][2] = 5.0;
+        acc.cascades[1][0][1][2] = 7.0;
+        acc.grid[1][0][1] = 1;
+    }
+    assert_eq!(values[6], 5.0);
+    assert_eq!(values[25], 7.0);
+    assert_eq!(values[32 + 4 + 1].to_bits(), 1);
+
+    let spans: Vec<_> = <NestedArrays as DynamicLayout>::get_named_field_spans(&layout).collect();
+    assert_eq!(spans.len(), 6 + 8 + 8);
+    assert!(spans.contains(&("weights[1][2]".to_string(), FieldSpan::new(24, 4))));
+    assert!(spans.contains(&("cascades[1][1][0]".to_string(), FieldSpan::new(104, 12))));
+    assert!(spans.contains(&("grid[1][1][0]".to_string(), FieldSpan::new(152, 4))));
+}
+
+#[test]
+fn overlapping_nested_arrays() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("weights",
+                                                                      NestedArrayField(&ArrayField(0, 4), 8)),
+                                                                     ("cascades",
+                                                                      NestedArrayField(&MatrixArrayField(32, 24, 12), 48)),
+                                                                     ("grid",
+                                                                      NestedArrayField(&NestedArrayField(&ArrayField(128, 4), 8), 16))];
+    assert!(NestedArrays::load_layout(&FIELDS).is_err());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct Doubles {
+    pub scale: f64,
+    pub center: DVec3,
+    pub transform: DMatrix2,
+    pub weights: [f64; 2],
+}
+
+#[test]
+fn double_precision() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("scale", PrimitiveField(0)),
+                                                                     ("center", PrimitiveField(32)),
+                                                                     ("transform", MatrixField(64, 16, MatrixOrder::ColumnMajor)),
+                                                                     ("weights", ArrayField(96, 16))];
+    let layout = Doubles::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 120);
+
+    let mut values = [0f64; 16];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        *acc.scale = 1.5;
+        acc.center.z = 2.0;
+        acc.transform[1][0] = 3.0;
+        acc.weights[1] = 4.0;
+    }
+    assert_eq!(values[0], 1.5);
+    assert_eq!(values[6], 2.0);
+    assert_eq!(values[10], 3.0);
+    assert_eq!(values[14], 4.0);
+}
+
+#[test]
+fn misaligned_double() {
+    const SCALE: &'static [(&'static str, LayoutInfo<'static>)] = &[("scale", PrimitiveField(4)),
+                                                                    ("center", PrimitiveField(32)),
+                                                                    ("transform", MatrixField(64, 16, MatrixOrder::ColumnMajor)),
+                                                                    ("weights", ArrayField(96, 16))];
+    const CENTER: &'static [(&'static str, LayoutInfo<'static>)] = &[("scale", PrimitiveField(0)),
+                                                                     ("center", PrimitiveField(36)),
+                                                                     ("transform", MatrixField(64, 16, MatrixOrder::ColumnMajor)),
+                                                                     ("weights", ArrayField(96, 16))];
+    const MATRIX_STRIDE: &'static [(&'static str, LayoutInfo<'static>)] = &[("scale", PrimitiveField(0)),
+                                                                            ("center", PrimitiveField(32)),
+                                                                            ("transform", MatrixField(64, 20, MatrixOrder::ColumnMajor)),
+                                                                            ("weights", ArrayField(104, 16))];
+    assert!(Doubles::load_layout(&SCALE).is_err());
+    assert!(Doubles::load_layout(&CENTER).is_err());
+    assert!(Doubles::load_layout(&MATRIX_STRIDE).is_err());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct Flags {
+    pub enabled: Bool32,
+    pub mask: BVec3,
+    pub list: [Bool32; 3],
+}
+
+#[test]
+fn boolean_fields() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("enabled", PrimitiveField(0)),
+                                                                     ("mask", PrimitiveField(16)),
+                                                                     ("list", ArrayField(32, 16))];
+    let layout = Flags::load_layout(&FIELDS).unwrap();
+
+    let mut values = [0u32; 32];
+    values[0] = 2;
+    values[5] = 0xffff_ffff;
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        assert!(acc.enabled.get());
+        assert_eq!(*acc.enabled, Bool32::new(true));
+        assert!(acc.mask.get(1));
+        assert!(!acc.mask.get(2));
+        acc.enabled.set(false);
+        acc.mask.set(1, true);
+        acc.mask.set(2, true);
+        acc.list[2].set(true);
+        assert!(!bool::from(acc.list[1]));
+    }
+    assert_eq!(values[0], 0);
+    assert_eq!(&values[4..7], &[0, 1, 1]);
+    assert_eq!(values[16], 1);
+}
+
+#[test]
+fn half_conversion() {
+    assert_eq!(F16::from_f32(1.0).to_bits(), 0x3c00);
+    assert_eq!(F16::from_f32(-2.0).to_bits(), 0xc000);
+    assert_eq!(F16::from_f32(65504.0).to_bits(), 0x7bff);
+    assert_eq!(F16::from_f32(65520.0).to_bits(), 0x7c00);
+    assert_eq!(F16::from_f32(2f32.powi(-24)).to_bits(), 0x0001);
+    assert_eq!(F16::from_f32(2f32.powi(-26)).to_bits(), 0x0000);
+    // 1 + 2^-11 is halfway between two halves and rounds to the even one
+    assert_eq!(F16::from_f32(1.0 + 2f32.powi(-11)).to_bits(), 0x3c00);
+    assert_eq!(F16::from_f32(1.0 + 3.0 * 2f32.powi(-11)).to_bits(), 0x3c02);
+
+    assert_eq!(F16::from_bits(0x0001).to_f32(), 2f32.powi(-24));
+    assert_eq!(F16::from_bits(0x3555).to_f32(), 0.333251953125);
+    assert_eq!(f32::from(F16::from(-0.5)), -0.5);
+    assert!(F16::from_f32(::std::f32::NAN).to_f32().is_nan());
+    assert_eq!(F16::from_f32(::std::f32::INFINITY).to_f32(), ::std::f32::INFINITY);
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct SmallTypes {
+    pub height: F16,
+    pub index: u16,
+    pub offset: i8,
+    pub color: U8Vec4,
+    pub uv: F16Vec2,
+    pub counts: [u16; 3],
+}
+
+#[test]
+fn small_storage_types() {
+    use dynamiclayout::buffer::AlignedBuffer;
+
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("height", PrimitiveField(0)),
+                                                                     ("index", PrimitiveField(2)),
+                                                                     ("offset", PrimitiveField(4)),
+                                                                     ("color", PrimitiveField(8)),
+                                                                     ("uv", PrimitiveField(12)),
+                                                                     ("counts", ArrayField(16, 2))];
+    let layout = SmallTypes::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 22);
+
+    let mut buffer = AlignedBuffer::for_layout::<SmallTypes>(&layout);
+    {
+        let mut data = buffer.data();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        *acc.height = F16::from_f32(1.0);
+        *acc.index = 0x0102;
+        *acc.offset = -1;
+        acc.color.z = 200;
+        acc.uv.y = F16::from_f32(-2.0);
+        acc.counts[2] = 7;
+    }
+    let bytes = buffer.as_slice();
+    assert_eq!(u16::from_ne_bytes([bytes[0], bytes[1]]), 0x3c00);
+    assert_eq!(u16::from_ne_bytes([bytes[2], bytes[3]]), 0x0102);
+    assert_eq!(bytes[4], 0xff);
+    assert_eq!(bytes[10], 200);
+    assert_eq!(u16::from_ne_bytes([bytes[14], bytes[15]]), 0xc000);
+    assert_eq!(u16::from_ne_bytes([bytes[20], bytes[21]]), 7);
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct ObjectRecord {
+    pub id: u64,
+    pub delta: i64,
+    pub range: U64Vec2,
+    pub timestamps: [u64; 2],
+}
+
+#[test]
+fn int64_fields() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("id", PrimitiveField(0)),
+                                                                     ("delta", PrimitiveField(8)),
+                                                                     ("range", PrimitiveField(16)),
+                                                                     ("timestamps", ArrayField(32, 8))];
+    let layout = ObjectRecord::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 48);
+
+    let mut values = [0u64; 6];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        *acc.id = 0x0123_4567_89ab_cdef;
+        *acc.delta = -2;
+        acc.range.y = 1 << 40;
+        acc.timestamps[1] = ::std::u64::MAX;
+    }
+    assert_eq!(values, [0x0123_4567_89ab_cdef, (-2i64) as u64, 0, 1 << 40, 0, ::std::u64::MAX]);
+
+    // Data only has to be 4-aligned, so the 64-bit fields need their own check
+    let mut values = [0u64; 7];
+    let mut data = Data::from_pod(&mut values).unwrap();
+    let error = <ObjectRecord as DynamicLayout>::make_accessor(&layout, data.reborrow().sub(4..52).unwrap()).err().unwrap();
+    assert_eq!(error.required_alignment, 8);
+    assert!(<ObjectRecord as DynamicLayout>::make_accessor(&layout, data.sub(8..56).unwrap()).is_ok());
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct Material {
+    pub albedo: TextureHandle,
+    pub target: ImageHandle,
+    pub lights: DevicePointer<Qux>,
+    pub layers: [DevicePointer<Foo>; 2],
+}
+
+#[test]
+fn handle_fields() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("albedo", PrimitiveField(0)),
+                                                                     ("target", PrimitiveField(8)),
+                                                                     ("lights", PrimitiveField(16)),
+                                                                     ("layers", ArrayField(32, 16))];
+    let layout = Material::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 56);
+
+    let mut values = [0u64; 7];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        *acc.albedo = TextureHandle::from_raw(0x1000_0000_0001);
+        *acc.target = ImageHandle::from_raw(2);
+        *acc.lights = DevicePointer::from_address(0xdead_0000);
+        assert!(acc.layers[1].is_null());
+        acc.layers[1] = DevicePointer::<Foo>::from_address(0xbeef_0000);
+    }
+    assert_eq!(values, [0x1000_0000_0001, 2, 0xdead_0000, 0, 0, 0, 0xbeef_0000]);
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, LayoutEnum)]
+pub enum ShadingMode {
+    Flat = 0,
+    Phong = 1,
+    Pbr = 5,
+}
+
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, LayoutEnum)]
+pub enum Direction {
+    Back = -1,
+    Forward = 1,
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct RenderModes {
+    #[dynamiclayout(discriminant)]
+    pub mode: ShadingMode,
+    #[dynamiclayout(discriminant)]
+    pub direction: Direction,
+    #[dynamiclayout(discriminant)]
+    pub passes: [ShadingMode; 3],
+}
+
+#[test]
+fn enum_fields() {
+    use dynamiclayout::tracking::DirtyTracker;
+
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("mode", PrimitiveField(0)),
+                                                                     ("direction", PrimitiveField(4)),
+                                                                     ("passes", ArrayField(16, 4))];
+    let layout = RenderModes::load_layout(&FIELDS).unwrap();
+
+    let mut values = [5, 0xffff_ffff, 0, 0, 0, 7, 0, 0u32];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        assert_eq!(acc.mode.get(), Ok(ShadingMode::Pbr));
+        assert_eq!(acc.direction.get(), Ok(Direction::Back));
+        assert_eq!(acc.passes.get(0), Ok(ShadingMode::Flat));
+        assert_eq!(acc.passes.get(1), Err(InvalidDiscriminant { bits: 7 }));
+        acc.mode.set(ShadingMode::Flat);
+        acc.direction.set(Direction::Forward);
+        acc.passes.set(1, ShadingMode::Phong);
+    }
+    assert_eq!(values, [0, 1, 0, 0, 0, 1, 0, 0]);
+
+    let tracker = DirtyTracker::new();
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_tracked_accessor(&mut data, &tracker).unwrap();
+        acc.passes.set(2, ShadingMode::Pbr);
+    }
+    assert_eq!(tracker.take().spans(), &[FieldSpan::new(24, 4)][..]);
+    assert_eq!(values[6], 5);
+}
+
+#[test]
+fn normalized_conversion() {
+    assert_eq!(Unorm8::from_f32(1.0).to_bits(), 255);
+    assert_eq!(Unorm8::from_f32(0.5).to_bits(), 128);
+    assert_eq!(Unorm8::from_f32(-3.0).to_bits(), 0);
+    assert_eq!(Unorm8::from_f32(::std::f32::NAN).to_bits(), 0);
+    assert_eq!(Snorm8::from_f32(-1.0).to_bits(), -127);
+    assert_eq!(Snorm8::from_bits(-128).to_f32(), -1.0);
+    assert_eq!(Unorm16::from_bits(0xffff).to_f32(), 1.0);
+    assert_eq!(Snorm16::from_f32(2.0).to_bits(), 0x7fff);
+    assert_eq!(f32::from(Snorm16::from(-0.5)), -16384.0 / 32767.0);
+
+    let packed = R10G10B10A2::new([1.0, 0.0, 0.5, 1.0]);
+    assert_eq!(packed.to_bits(), 0x3ff | 512 << 20 | 3 << 30);
+    assert_eq!(packed.get(), [1.0, 0.0, 512.0 / 1023.0, 1.0]);
+
+    let packed = R11G11B10F::new([1.0, 0.5, 2.0]);
+    assert_eq!(packed.to_bits(), 0x3c0 | 0x380 << 11 | 0x200 << 22);
+    assert_eq!(packed.get(), [1.0, 0.5, 2.0]);
+    // Negative values become zero, too large ones the largest finite value
+    assert_eq!(R11G11B10F::new([-1.0, 1.0e6, ::std::f32::INFINITY]).get(), [0.0, 65024.0, ::std::f32::INFINITY]);
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+pub struct PackedVertex {
+    pub color: Unorm8Vec4,
+    pub normal: R10G10B10A2,
+    pub tangent: Snorm16Vec3,
+    pub emission: R11G11B10F,
+}
+
+#[test]
+fn packed_vertex_formats() {
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("color", PrimitiveField(0)),
+                                                                     ("normal", PrimitiveField(4)),
+                                                                     ("tangent", PrimitiveField(8)),
+                                                                     ("emission", PrimitiveField(16))];
+    let layout = PackedVertex::load_layout(&FIELDS).unwrap();
+    assert_eq!(layout.required_data_len(), 20);
+
+    let mut values = [0u32; 5];
+    {
+        let mut data = Data::from_pod(&mut values).unwrap();
+        let mut acc = layout.make_accessor(&mut data).unwrap();
+        acc.color.set([1.0, 0.0, 0.0, 1.0]);
+        acc.normal.set([0.0, 0.0, 1.0, 0.0]);
+        acc.tangent.set([-1.0, 0.0, 1.0]);
+        acc.emission.set([0.0, 0.0, 1.0]);
+        assert_eq!(acc.color.get(), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(acc.tangent.get(), [-1.0, 0.0, 1.0]);
+    }
+    assert_eq!(values[0], u32::from_ne_bytes([255, 0, 0, 255]));
+    assert_eq!(values[1], 0x3ff << 20);
+    assert_eq!(values[4], 0x1e0 << 22);
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout, VertexLayout)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: R10G10B10A2,
+    pub color: Unorm8Vec4,
+}
+
+#[test]
+fn vertex_buffer_layout() {
+    let layout = VertexBufferLayout::new::<Vertex>(InputRate::Vertex).unwrap();
+    assert_eq!(layout.stride(), 20);
+    assert_eq!(layout.attributes(), &[
+        VertexAttribute { name: "position", offset: 0, format: VertexFormat::Vector(ComponentType::Float32, 3) },
+        VertexAttribute { name: "normal", offset: 12, format: VertexFormat::R10G10B10A2 },
+        VertexAttribute { name: "color", offset: 16, format: VertexFormat::Vector(ComponentType::Unorm8, 4) },
+    ][..]);
+
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("position", PrimitiveField(0)),
+                                                                     ("normal", PrimitiveField(12)),
+                                                                     ("color", PrimitiveField(16))];
+    let layout = VertexBufferLayout::from_layout::<Vertex>(Vertex::load_layout(&FIELDS).unwrap(), 24, InputRate::Vertex).unwrap();
+    assert_eq!(layout.stride(), 24);
+    assert!(VertexBufferLayout::from_layout::<Vertex>(Vertex::load_layout(&FIELDS).unwrap(), 16, InputRate::Vertex).is_err());
+
+    // The last vertex does not need the padding at the end of the stride
+    let mut values = [0u32; 17];
+    {
+        let mut buffer = VertexBuffer::new(Data::from_pod(&mut values).unwrap());
+        assert_eq!(buffer.len(&layout), 3);
+        for i in 0..3 {
+            let mut vertex = buffer.element::<Vertex>(&layout, i).unwrap();
+            vertex.position.y = i as f32;
+            vertex.color.set([1.0, 1.0, 1.0, 1.0]);
+        }
+        assert!(buffer.element::<Vertex>(&layout, 3).is_err());
+        assert!(buffer.element::<Vertex>(&layout, usize::max_value()).is_err());
+    }
+    assert_eq!(values[7], 1.0f32.to_bits());
+    assert_eq!(values[13], 2.0f32.to_bits());
+    assert_eq!(values[16], 0xffff_ffff);
+}
+
+#[derive(Debug, Copy, Clone, SpecializationConstants)]
+pub struct ShaderOptions {
+    #[dynamiclayout(constant_id = 2)]
+    pub use_shadows: Bool32,
+    #[dynamiclayout(constant_id = 0)]
+    pub sample_count: u16,
+    #[dynamiclayout(constant_id = 7)]
+    pub exposure: f32,
+}
+
+#[test]
+fn specialization_constants() {
+    let options = ShaderOptions { use_shadows: Bool32::from(true), sample_count: 4, exposure: 1.5 };
+    let mut info = options.specialization_info();
+    assert_eq!(info.entries(), &[
+        SpecializationMapEntry { constant_id: 2, offset: 0, size: 4 },
+        SpecializationMapEntry { constant_id: 0, offset: 4, size: 2 },
+        // Aligned to its size after the 16-bit constant
+        SpecializationMapEntry { constant_id: 7, offset: 8, size: 4 },
+    ][..]);
+    let data = info.data();
+    assert_eq!(data.len(), 12);
+    assert_eq!(u32::from_ne_bytes([data[0], data[1], data[2], data[3]]), 1);
+    assert_eq!(u16::from_ne_bytes([data[4], data[5]]), 4);
+    assert_eq!(f32::from_ne_bytes([data[8], data[9], data[10], data[11]]), 1.5);
+    assert_eq!(info.entry(7).map(|entry| entry.offset), Some(8));
+    assert_eq!(info.push(2, 0u32).unwrap_err().constant_id, 2);
+    assert_eq!(info.entries().len(), 3);
+}
+
+#[test]
+fn push_constant_ranges() {
+    const QUX_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("one", PrimitiveField(0)),
+                                                                         ("four", PrimitiveField(4))];
+    let matrix_layout = matrix_layout();
+    let qux_layout = Qux::load_layout(&QUX_FIELDS).unwrap();
+
+    let mut push_constants = PushConstants::new();
+    let transform = push_constants.add::<Matrix>(&matrix_layout, ShaderStages::VERTEX).unwrap();
+    let material = push_constants.add::<Qux>(&qux_layout, ShaderStages::FRAGMENT | ShaderStages::COMPUTE).unwrap();
+    assert_eq!(push_constants.ranges(), &[
+        PushConstantRange { stages: ShaderStages::VERTEX, offset: 0, size: 64 },
+        PushConstantRange { stages: ShaderStages(0x30), offset: 64, size: 20 },
+    ][..]);
+    {
+        let mut acc = push_constants.accessor::<Qux>(material, &qux_layout).unwrap();
+        *acc.one = 2.0;
+    }
+    {
+        let mut acc = push_constants.accessor::<Matrix>(transform, &matrix_layout).unwrap();
+        acc.matrix[3][3] = 1.0;
+    }
+    assert_eq!(push_constants.as_slice().len(), 84);
+    assert_eq!(&push_constants.range_slice(material).unwrap()[..4], &2.0f32.to_ne_bytes()[..]);
+    assert!(push_constants.range_slice(2).is_none());
+    assert!(push_constants.accessor::<Qux>(2, &qux_layout).is_err());
+    assert_eq!(&push_constants.as_slice()[60..64], &1.0f32.to_ne_bytes()[..]);
+
+    // A second matrix does not fit into the remaining 44 bytes
+    assert_eq!(push_constants.add::<Matrix>(&matrix_layout, ShaderStages::GEOMETRY).unwrap_err(),
+               PushConstantError::LimitExceeded { required_len: 148, limit: 128 });
+    assert!(PushConstants::with_limit(256).add::<Matrix>(&matrix_layout, ShaderStages::GEOMETRY).is_ok());
+    // Every stage can only be in one range
+    assert_eq!(push_constants.add::<Qux>(&qux_layout, ShaderStages::ALL_GRAPHICS).unwrap_err(),
+               PushConstantError::StagesOverlap(ShaderStages(0x11)));
+
+    // Ranges of blocks with doubles start at a multiple of 8
+    const DOUBLES_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("scale", PrimitiveField(0)),
+                                                                             ("center", PrimitiveField(32)),
+                                                                             ("transform", MatrixField(64, 16, MatrixOrder::ColumnMajor)),
+                                                                             ("weights", ArrayField(96, 16))];
+    let doubles_layout = Doubles::load_layout(&DOUBLES_FIELDS).unwrap();
+    let mut push_constants = PushConstants::with_limit(256);
+    push_constants.add::<Qux>(&qux_layout, ShaderStages::VERTEX).unwrap();
+    let doubles = push_constants.add::<Doubles>(&doubles_layout, ShaderStages::FRAGMENT).unwrap();
+    assert_eq!(push_constants.ranges()[doubles], PushConstantRange { stages: ShaderStages::FRAGMENT, offset: 24, size: 120 });
+    {
+        let mut acc = push_constants.accessor::<Doubles>(doubles, &doubles_layout).unwrap();
+        *acc.scale = 1.5;
+    }
+    assert_eq!(&push_constants.as_slice()[24..32], &1.5f64.to_ne_bytes()[..]);
+}
+
+#[derive(Default)]
+struct UniformCommands(::std::cell::RefCell<Vec<(i32, UniformValue)>>);
+
+impl UniformSink for UniformCommands {
+    fn set(&self, location: i32, value: UniformValue) {
+        self.0.borrow_mut().push((location, value));
+    }
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+#[dynamiclayout(default_block)]
+pub struct Light {
+    pub color: Vec3,
+    pub enabled: Bool32,
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, DynamicLayout)]
+#[dynamiclayout(default_block)]
+pub struct LegacyUniforms {
+    pub transform: Matrix2x3,
+    pub offsets: [Vec2; 3],
+    pub lights: [Light; 2],
+}
+
+#[test]
+fn default_block_uniforms() {
+    const LIGHT0: &'static [(&'static str, LayoutInfo<'static>)] = &[("color", LocationField(5)), ("enabled", LocationField(6))];
+    const LIGHT1: &'static [(&'static str, LayoutInfo<'static>)] = &[("color", LocationField(7)), ("enabled", LocationField(-1))];
+    const FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("transform", LocationField(0)),
+                                                                     ("offsets", LocationField(2)),
+                                                                     ("lights", StructArrayField(&[&LIGHT0, &LIGHT1]))];
+    let layout = load_uniform_layout::<LegacyUniforms>(&FIELDS).unwrap();
+    let commands = UniformCommands::default();
+    {
+        let acc = layout.make_accessor(&commands);
+        acc.transform.set(Matrix2x3::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+        acc.offsets.set(2, Vec2::new(0.5, -0.5));
+        acc.lights[1].color.set(Vec3::new(1.0, 1.0, 0.0));
+        acc.lights[0].enabled.set(Bool32::from(true));
+        assert_eq!(acc.offsets.len(), 3);
+    }
+    assert_eq!(&commands.0.borrow()[..], &[
+        (0, UniformValue::Matrix(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])),
+        (4, UniformValue::Float(vec![0.5, -0.5])),
+        (7, UniformValue::Float(vec![1.0, 1.0, 0.0])),
+        (6, UniformValue::Int(vec![1])),
+    ][..]);
+
+    // Block layouts are not location layouts
+    const OFFSETS: &'static [(&'static str, LayoutInfo<'static>)] = &[("color", PrimitiveField(0)), ("enabled", PrimitiveField(12))];
+    assert!(load_uniform_layout::<Light>(&OFFSETS).is_err());
+}
+
+#[test]
+fn program_layout() {
+    const QUX_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("one", PrimitiveField(0)),
+                                                                         ("four", PrimitiveField(16))];
+    const MATRIX_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("matrix", ArrayField(0, 16))];
+    let mut program = ProgramLayout::new();
+    program.add_block(BlockDescriptor {
+        name: "Material".to_string(), kind: BlockKind::Uniform, set: 0, binding: 1, data_size: 32, layout: &QUX_FIELDS,
+    });
+    program.add_block(BlockDescriptor {
+        name: "Transform".to_string(), kind: BlockKind::Storage, set: 1, binding: 0, data_size: 64, layout: &MATRIX_FIELDS,
+    });
+
+    let material = program.load_block::<Qux>("Material").unwrap();
+    assert_eq!((material.kind(), material.set(), material.binding(), material.data_size()), (BlockKind::Uniform, 0, 1, 32));
+    assert_eq!(material.layout().required_data_len(), 32);
+    let transform = program.load_block::<Matrix>("Transform").unwrap();
+    assert_eq!((transform.kind(), transform.set(), transform.binding()), (BlockKind::Storage, 1, 0));
+    assert_eq!(program.block_at(BlockKind::Storage, 1, 0).map(|block| &block.name[..]), Some("Transform"));
+
+    assert!(program.load_block::<Qux>("Lights").is_err());
+    // The block has to be large enough for every field of the type
+    program.add_block(BlockDescriptor {
+        name: "Material".to_string(), kind: BlockKind::Uniform, set: 0, binding: 1, data_size: 16, layout: &QUX_FIELDS,
+    });
+    assert_eq!(program.blocks().len(), 2);
+    assert!(program.load_block::<Qux>("Material").is_err());
+    // A block bound at the same place replaces the old one as well
+    program.add_block(BlockDescriptor {
+        name: "Camera".to_string(), kind: BlockKind::Storage, set: 1, binding: 0, data_size: 64, layout: &MATRIX_FIELDS,
+    });
+    assert_eq!(program.blocks().len(), 2);
+    assert!(program.block("Transform").is_none());
+    assert_eq!(program.block_at(BlockKind::Storage, 1, 0).map(|block| &block.name[..]), Some("Camera"));
+    // Uniform and storage blocks have their own binding points
+    program.add_block(BlockDescriptor {
+        name: "Lights".to_string(), kind: BlockKind::Uniform, set: 1, binding: 0, data_size: 32, layout: &QUX_FIELDS,
+    });
+    assert_eq!(program.blocks().len(), 3);
+    assert_eq!(program.block_at(BlockKind::Uniform, 1, 0).map(|block| &block.name[..]), Some("Lights"));
+    assert_eq!(program.block_at(BlockKind::Storage, 1, 0).map(|block| &block.name[..]), Some("Camera"));
+}
+
+#[test]
+fn declared_sizes() {
+    use dynamiclayout::load::SizedStructLayout;
+    use dynamiclayout::buffer::AlignedBuffer;
+
+    const QUX_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("one", PrimitiveField(0)),
+                                                                         ("four", PrimitiveField(16))];
+    assert_eq!(Qux::load_layout(&QUX_FIELDS).unwrap().required_data_len(), 32);
+    let layout = Qux::load_layout(&SizedStructLayout { fields: &QUX_FIELDS, size: 48 }).unwrap();
+    assert_eq!(layout.required_data_len(), 48);
+    assert_eq!(AlignedBuffer::for_layout::<Qux>(&layout).len(), 48);
+    let mut short = [0f32; 8];
+    assert!(layout.make_accessor(&mut Data::from_pod(&mut short).unwrap()).is_err());
+
+    // The declared element size is measured from the first field, and must fit into the stride
+    const PARTICLE_LAYOUT: LayoutInfo<'static> = StructField(&SizedStructLayout { fields: &PARTICLE_FIELDS, size: 40 });
+    const NARROW_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("count", PrimitiveField(0)),
+                                                                            ("particles", RuntimeArrayField(&PARTICLE_LAYOUT, 32))];
+    const WIDE_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("count", PrimitiveField(0)),
+                                                                          ("particles", RuntimeArrayField(&PARTICLE_LAYOUT, 48))];
+    assert!(ParticleBuffer::load_layout(&NARROW_FIELDS).is_err());
+    let layout = ParticleBuffer::load_layout(&WIDE_FIELDS).unwrap();
+    // Elements end at 56, 104, 152 and 200, the fifth one would need 8 more bytes than there are
+    let mut values = [0f32; 60];
+    assert_eq!(layout.make_accessor(&mut Data::from_pod_slice(&mut values[..]).unwrap()).unwrap().particles.len(), 4);
+
+    // A nested struct counts with its declared size in the length of its parent
+    const SIZED_BAR_LAYOUT: LayoutInfo<'static> = StructField(&SizedStructLayout { fields: &BAR_FIELDS, size: 96 });
+    const SIZED_FOO_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("three", PrimitiveField(0)),
+                                                                               ("one", PrimitiveField(12)),
+                                                                               ("four", PrimitiveField(16)),
+                                                                               ("two", PrimitiveField(32)),
+                                                                               ("compound", SIZED_BAR_LAYOUT)];
+    assert_eq!(Foo::load_layout(&FOO_FIELDS).unwrap().required_data_len(), 124);
+    assert_eq!(Foo::load_layout(&SIZED_FOO_FIELDS).unwrap().required_data_len(), 136);
+
+    // The declared size of an element of a struct array must end before the next element
+    const WIDE_QUX_LAYOUT: LayoutInfo<'static> = StructField(&SizedStructLayout { fields: &QUX_FIELDS_0, size: 24 });
+    const FITTING_QUX_LAYOUT: LayoutInfo<'static> = StructField(&SizedStructLayout { fields: &QUX_FIELDS_0, size: 20 });
+    const OVERLAPPING_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("array",
+                                               StructArrayField(&[&WIDE_QUX_LAYOUT, &QUX_LAYOUT_1]))];
+    const FITTING_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("array",
+                                               StructArrayField(&[&FITTING_QUX_LAYOUT, &QUX_LAYOUT_1]))];
+    assert!(StructArray::load_layout(&OVERLAPPING_FIELDS).is_err());
+    assert!(StructArray::load_layout(&FITTING_FIELDS).is_ok());
+
+    // The highest element counts with its declared size, also when it is not listed last
+    const SIZED_QUX_LAYOUT_1: LayoutInfo<'static> = StructField(&SizedStructLayout { fields: &QUX_FIELDS_1, size: 32 });
+    const REVERSED_FIELDS: &'static [(&'static str, LayoutInfo<'static>)] = &[("array",
+                                                StructArrayField(&[&SIZED_QUX_LAYOUT_1, &QUX_LAYOUT_0]))];
+    assert_eq!(StructArray::load_layout(&REVERSED_FIELDS).unwrap().required_data_len(), 52);
+}
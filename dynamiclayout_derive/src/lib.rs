@@ -8,31 +8,209 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 use quote::{Tokens};
-use syn::{Body, VariantData, Field, Ident, Ty, ConstExpr};
+use syn::{Body, VariantData, Variant, Field, Ident, Ty, ConstExpr, Attribute, MetaItem, NestedMetaItem, Lit};
 
-struct ArrayFieldInfo<'a> {index: usize, ty: &'a Ty, size: &'a ConstExpr, layout: Ident, accessor: Ident}
+struct ArrayDimension<'a> {size: &'a ConstExpr, layout: Ident, accessor: Ident}
 
-#[proc_macro_derive(DynamicLayout)]
+// Dimensions are stored innermost first, arrays of arrays have more than one
+struct ArrayFieldInfo<'a> {index: usize, element: Tokens, dimensions: Vec<ArrayDimension<'a>>}
+
+impl<'a> ArrayFieldInfo<'a> {
+    // Every dimension inside the given one is wrapped in ArrayOf, with its own array helpers
+    fn element_type(&self, dimension: usize, lifetime: &Tokens) -> Tokens {
+        self.dimensions[..dimension].iter().fold(self.element.clone(), |inner, inner_dimension| {
+            let layout_helper = &inner_dimension.layout;
+            let accessor_helper = &inner_dimension.accessor;
+            quote! { ArrayOf<#inner, #layout_helper<#lifetime>, #accessor_helper<#lifetime>> }
+        })
+    }
+
+    fn trait_tokens(&self, trait_name: &str, lifetime: Tokens) -> Tokens {
+        let outer = self.dimensions.len() - 1;
+        let element_type = self.element_type(outer, &lifetime);
+        let trait_name = Ident::from(trait_name);
+        let layout_helper = &self.dimensions[outer].layout;
+        let accessor_helper = &self.dimensions[outer].accessor;
+        quote! { <#element_type as #trait_name<#lifetime, #layout_helper<#lifetime>, #accessor_helper<#lifetime>>> }
+    }
+}
+
+#[proc_macro_derive(DynamicLayout, attributes(dynamiclayout))]
 pub fn derive_dynamiclayout(input: TokenStream) -> TokenStream {
     let input_string = input.to_string();
     let ast = syn::parse_macro_input(&input_string).unwrap();
     if let Body::Struct(VariantData::Struct(ref fields)) = ast.body {
-        let output = make_types(&ast.ident, fields);
+        let output = make_types(&ast.ident, fields, is_packed(&ast.attrs), is_default_block(&ast.attrs));
+        output.parse().unwrap()
+    } else {
+        panic!("Only structs with named fields are supported")
+    }
+}
+
+#[proc_macro_derive(LayoutEnum)]
+pub fn derive_layout_enum(input: TokenStream) -> TokenStream {
+    let input_string = input.to_string();
+    let ast = syn::parse_macro_input(&input_string).unwrap();
+    if let Body::Enum(ref variants) = ast.body {
+        let output = make_layout_enum(&ast.ident, variants, enum_repr(&ast.attrs));
+        output.parse().unwrap()
+    } else {
+        panic!("LayoutEnum can only be derived for enums")
+    }
+}
+
+#[proc_macro_derive(VertexLayout)]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input_string = input.to_string();
+    let ast = syn::parse_macro_input(&input_string).unwrap();
+    if !is_repr_c(&ast.attrs) {
+        panic!("VertexLayout requires #[repr(C)]")
+    }
+    if let Body::Struct(VariantData::Struct(ref fields)) = ast.body {
+        let output = make_vertex_layout(&ast.ident, fields);
+        output.parse().unwrap()
+    } else {
+        panic!("Only structs with named fields are supported")
+    }
+}
+
+// The offsets and the stride are those of the struct as the compiler laid it out
+fn make_vertex_layout(original_name: &Ident, fields: &Vec<Field>) -> Tokens {
+    let mod_name = Ident::new(original_name.to_string().to_lowercase() + "_vertexlayout_derive_mod");
+    let idents: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let names: Vec<_> = idents.iter().map(|ident| ident.to_string()).collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    quote!{
+        #[doc(hidden)]
+        pub mod #mod_name {
+            #![allow(unused_imports)]
+            //use super::*;
+            extern crate dynamiclayout;
+            use dynamiclayout::vertex::{VertexLayout, VertexAttributeType, VertexFormat};
+            use super::#original_name as OriginalType;
+
+            impl VertexLayout for OriginalType {
+                fn attributes() -> Vec<(&'static str, usize, VertexFormat)> {
+                    vec![ #( (#names, ::std::mem::offset_of!(OriginalType, #idents), <#types as VertexAttributeType>::vertex_format()) ),* ]
+                }
+
+                fn stride() -> usize {
+                    ::std::mem::size_of::<OriginalType>()
+                }
+            }
+        }
+    }
+}
+
+#[proc_macro_derive(SpecializationConstants, attributes(dynamiclayout))]
+pub fn derive_specialization_constants(input: TokenStream) -> TokenStream {
+    let input_string = input.to_string();
+    let ast = syn::parse_macro_input(&input_string).unwrap();
+    if let Body::Struct(VariantData::Struct(ref fields)) = ast.body {
+        let output = make_specialization_constants(&ast.ident, fields);
         output.parse().unwrap()
     } else {
         panic!("Only structs with named fields are supported")
     }
 }
 
-fn make_types(original_name: &Ident, fields: &Vec<Field>) -> Tokens {
+fn constant_id(field: &Field) -> u32 {
+    field.attrs.iter().filter_map(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "dynamiclayout" => items.iter().filter_map(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::NameValue(ref key, Lit::Int(id, _))) if key == "constant_id" => {
+                if id > u32::max_value() as u64 {
+                    panic!("Specialization constant id {} does not fit into a u32", id);
+                }
+                Some(id as u32)
+            },
+            _ => None
+        }).next(),
+        _ => None
+    }).next().expect("Every specialization constant needs a #[dynamiclayout(constant_id = N)] attribute")
+}
+
+fn make_specialization_constants(original_name: &Ident, fields: &Vec<Field>) -> Tokens {
+    let mod_name = Ident::new(original_name.to_string().to_lowercase() + "_specialization_derive_mod");
+    let names: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let ids: Vec<_> = fields.iter().map(constant_id).collect();
+    for (i, id) in ids.iter().enumerate() {
+        if ids[..i].contains(id) {
+            panic!("Specialization constant id {} is used more than once", id);
+        }
+    }
+    quote!{
+        #[doc(hidden)]
+        pub mod #mod_name {
+            extern crate dynamiclayout;
+            use dynamiclayout::specialization::{SpecializationConstants, SpecializationInfo};
+            use super::#original_name as OriginalType;
+
+            impl SpecializationConstants for OriginalType {
+                fn specialization_info(&self) -> SpecializationInfo {
+                    let mut info = SpecializationInfo::new();
+                    // The ids were checked to be unique when deriving
+                    #( info.push(#ids, self.#names).unwrap(); )*
+                    info
+                }
+            }
+        }
+    }
+}
+
+// Discriminants are stored as 32 bits, so the size of the discriminant has to be fixed
+fn enum_repr(attrs: &Vec<Attribute>) -> Ident {
+    attrs.iter().filter_map(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "repr" => items.iter().filter_map(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) if word == "u32" || word == "i32" => Some(word.clone()),
+            _ => None
+        }).next(),
+        _ => None
+    }).next().expect("LayoutEnum requires #[repr(u32)] or #[repr(i32)]")
+}
+
+fn make_layout_enum(original_name: &Ident, variants: &Vec<Variant>, repr: Ident) -> Tokens {
+    let mod_name = Ident::new(original_name.to_string().to_lowercase() + "_layoutenum_derive_mod");
+    let names: Vec<_> = variants.iter().map(|variant| match variant.data {
+        VariantData::Unit => &variant.ident,
+        _ => panic!("LayoutEnum can only be derived for C-like enums")
+    }).collect();
+    let discriminants: Vec<_> = names.iter().map(|name| quote! { OriginalType::#name as #repr as u32 }).collect();
+    quote!{
+        #[doc(hidden)]
+        pub mod #mod_name {
+            extern crate dynamiclayout;
+            use dynamiclayout::enums::LayoutEnum;
+            use super::#original_name as OriginalType;
+
+            impl LayoutEnum for OriginalType {
+                fn to_bits(self) -> u32 {
+                    self as #repr as u32
+                }
+
+                fn from_bits(bits: u32) -> Option<OriginalType> {
+                    #( if bits == #discriminants { return Some(OriginalType::#names); } )*
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn make_types(original_name: &Ident, fields: &Vec<Field>, packed: bool, default_block: bool) -> Tokens {
     let mod_name = Ident::new(original_name.to_string().to_lowercase() + "_dynamiclayout_derive_mod");
     let array_fields = collect_array_fields(fields);
     let layout_struct = layout_struct(fields, &array_fields);
     let accessor_struct = accessor_struct(fields, &array_fields);
+    let tracked_accessor_struct = tracked_accessor_struct(fields, &array_fields);
     let impl_dynamic_layout = impl_dynamic_layout();
     let impl_field = impl_field(fields, &array_fields);
     let impl_array_field = impl_array_field();
+    let array_accessor = array_accessor(fields);
+    let impl_tracked_field = impl_tracked_field(fields, &array_fields);
+    let impl_tracked_array_field = impl_tracked_array_field();
     let array_helpers = make_array_helpers(&array_fields);
+    let impl_pod = impl_pod(fields, packed);
+    let impl_uniform_field = if default_block { impl_uniform_field(fields, &array_fields) } else { quote!{} };
     quote!{
         #[doc(hidden)]
         pub mod #mod_name {
@@ -41,22 +219,35 @@ fn make_types(original_name: &Ident, fields: &Vec<Field>) -> Tokens {
             use ::std::mem;
             use ::std::ops;
             extern crate dynamiclayout;
-            use dynamiclayout::{DynamicLayout, LayoutError, AccessorError, Field, ArrayField, ArrayHelper, Data, OffsetType};
+            use dynamiclayout::{DynamicLayout, LayoutError, AccessorError, Field, ArrayField, ArrayHelper, Data, OffsetType, LengthType, Pod};
+            use dynamiclayout::{TrackedField, TrackedArrayField};
             use dynamiclayout::load::{LoadStructLayout, LayoutInfo, FieldSpan};
+            use dynamiclayout::tracking::DirtyTracker;
+            use dynamiclayout::accessor::{PrimitiveArrayAccessor, ColumnAccessor};
+            use dynamiclayout::nested_array::ArrayOf;
+            use dynamiclayout::enums::EnumField;
+            use dynamiclayout::uniforms::{UniformField, UniformArrayField, UniformSink};
             use super::#original_name as OriginalType;
 
             pub struct GeneratedLayout {
                 fields: GeneratedLayoutFields,
                 len: OffsetType,
+                declared_len: Option<LengthType>,
             }
 
             impl GeneratedLayout {
                 pub fn make_accessor<'a>(&self, data: &'a mut Data) -> Result<GeneratedAccessor<'a>, AccessorError> {
-                    <OriginalType as DynamicLayout<'a>>::make_accessor(self, data)
+                    <OriginalType as DynamicLayout<'a>>::make_accessor(self, data.reborrow())
+                }
+
+                pub fn make_tracked_accessor<'a>(&self, data: &'a mut Data, tracker: &'a DirtyTracker) -> Result<GeneratedTrackedAccessor<'a>, AccessorError> {
+                    <OriginalType as DynamicLayout<'a>>::make_tracked_accessor(self, data.reborrow(), tracker)
                 }
 
+                // Includes the trailing padding of the declared size, if there is one. The whole
+                // block starts at offset 0, so here the declared size is measured from there.
                 pub fn required_data_len(&self) -> usize {
-                    self.len as usize
+                    ::std::cmp::max(self.len as usize, self.declared_len.unwrap_or(0) as usize)
                 }
             }
 
@@ -64,23 +255,104 @@ fn make_types(original_name: &Ident, fields: &Vec<Field>) -> Tokens {
 
             #accessor_struct
 
+            #tracked_accessor_struct
+
             #impl_dynamic_layout
 
             #impl_field
 
             #impl_array_field
 
+            #array_accessor
+
+            #impl_tracked_field
+
+            #impl_tracked_array_field
+
             #array_helpers
+
+            #impl_pod
+
+            #impl_uniform_field
+        }
+    }
+}
+
+// Fields of C-like enum types are marked with #[dynamiclayout(discriminant)], the enum implements LayoutEnum
+fn is_enum_field(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "dynamiclayout" => items.iter().any(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => word == "discriminant",
+            _ => false
+        }),
+        _ => false
+    })
+}
+
+fn field_type(field: &Field) -> Tokens {
+    let ty = &field.ty;
+    if is_enum_field(field) {
+        quote! { EnumField<#ty> }
+    } else {
+        quote! { #ty }
+    }
+}
+
+// Structs used as default-block uniforms are marked with #[dynamiclayout(default_block)]
+fn is_default_block(attrs: &Vec<Attribute>) -> bool {
+    attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "dynamiclayout" => items.iter().any(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => word == "default_block",
+            _ => false
+        }),
+        _ => false
+    })
+}
+
+fn is_packed(attrs: &Vec<Attribute>) -> bool {
+    attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "repr" => items.iter().any(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => word == "packed",
+            _ => false
+        }),
+        _ => false
+    })
+}
+
+fn is_repr_c(attrs: &Vec<Attribute>) -> bool {
+    attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref name, ref items) if name == "repr" => items.iter().any(|item| match *item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => word == "C",
+            _ => false
+        }),
+        _ => false
+    })
+}
+
+// Only packed structs are free of padding, and then only if every field is Pod as well.
+// Enums have invalid bit patterns, so structs containing them are never Pod.
+fn impl_pod(fields: &Vec<Field>, packed: bool) -> Tokens {
+    if !packed || fields.iter().any(is_enum_field) {
+        return quote!{};
+    }
+    let field_types = fields.iter().map(|field| &field.ty);
+    quote!{
+        unsafe impl Pod for OriginalType {}
+
+        #[allow(dead_code)]
+        fn assert_fields_are_pod() {
+            fn is_pod<T: Pod>() {}
+            #( is_pod::<#field_types>(); )*
         }
     }
 }
 
 fn make_array_helpers(fields: &Vec<ArrayFieldInfo>) -> Tokens {
-    let helpers: Vec<_> = fields.iter().map(|field| {
-        let ty = field.ty;
-        let size = field.size;
-        let layout_helper_name = &field.layout;
-        let accessor_helper_name = &field.accessor;
+    let helpers: Vec<_> = fields.iter().flat_map(|field| field.dimensions.iter().enumerate().map(move |(k, dimension)| {
+        let ty = field.element_type(k, &quote! { 'a });
+        let size = dimension.size;
+        let layout_helper_name = &dimension.layout;
+        let accessor_helper_name = &dimension.accessor;
         quote!{
             pub struct #layout_helper_name<'a> (mem::ManuallyDrop<[<#ty as Field<'a>>::Layout; #size]>);
             unsafe impl<'a> ArrayHelper<'a> for #layout_helper_name<'a> {
@@ -95,6 +367,8 @@ fn make_array_helpers(fields: &Vec<ArrayFieldInfo>) -> Tokens {
 
                 fn array_as_slice(array: &Self::ArrayType) -> &[Self::Item] { &array[..] }
 
+                fn array_as_mut_slice(array: &mut Self::ArrayType) -> &mut [Self::Item] { &mut array[..] }
+
                 fn into_array(self) -> Self::ArrayType  { mem::ManuallyDrop::into_inner(self.0) }
             }
             pub struct #accessor_helper_name<'a> (mem::ManuallyDrop<[<#ty as Field<'a>>::Accessor; #size]>);
@@ -110,28 +384,53 @@ fn make_array_helpers(fields: &Vec<ArrayFieldInfo>) -> Tokens {
 
                 fn array_as_slice(array: &Self::ArrayType) -> &[Self::Item] { &array[..] }
 
+                fn array_as_mut_slice(array: &mut Self::ArrayType) -> &mut [Self::Item] { &mut array[..] }
+
                 fn into_array(self) -> Self::ArrayType  { mem::ManuallyDrop::into_inner(self.0) }
             }
         }
-    }).collect();
+    })).collect();
     quote!{
         #(#helpers)*
     }
 }
 
 fn collect_array_fields(fields: &Vec<Field>) -> Vec<ArrayFieldInfo> {
-    fields.iter().enumerate().filter_map(|(i, f)| match f.ty {
-        Ty::Array(ref ty, ref size) => Some(ArrayFieldInfo {index: i, ty: ty.as_ref(), size, layout: layout_array_helper_name(i), accessor: accessor_array_helper_name(i)}),
-        _ => None
+    fields.iter().enumerate().filter_map(|(i, f)| {
+        let mut sizes = Vec::new();
+        let mut element = &f.ty;
+        while let Ty::Array(ref ty, ref size) = *element {
+            sizes.push(size);
+            element = ty.as_ref();
+        }
+        if sizes.is_empty() {
+            return None;
+        }
+        let outer = sizes.len() - 1;
+        let dimensions = sizes.into_iter().rev().enumerate().map(|(k, size)| ArrayDimension {
+            size,
+            layout: layout_array_helper_name(i, k, outer),
+            accessor: accessor_array_helper_name(i, k, outer)
+        }).collect();
+        let element = if is_enum_field(f) { quote! { EnumField<#element> } } else { quote! { #element } };
+        Some(ArrayFieldInfo {index: i, element, dimensions})
     }).collect()
 }
 
-fn layout_array_helper_name(i: usize) -> Ident {
-    format!("LayoutArrayHelper{}", i).into()
+fn layout_array_helper_name(i: usize, dimension: usize, outer: usize) -> Ident {
+    if dimension == outer {
+        format!("LayoutArrayHelper{}", i).into()
+    } else {
+        format!("LayoutArrayHelper{}_{}", i, dimension).into()
+    }
 }
 
-fn accessor_array_helper_name(i: usize) -> Ident {
-    format!("AccessorArrayHelper{}", i).into()
+fn accessor_array_helper_name(i: usize, dimension: usize, outer: usize) -> Ident {
+    if dimension == outer {
+        format!("AccessorArrayHelper{}", i).into()
+    } else {
+        format!("AccessorArrayHelper{}_{}", i, dimension).into()
+    }
 }
 
 fn layout_struct(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens {
@@ -164,16 +463,68 @@ fn accessor_struct(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> T
     }
 }
 
+fn tracked_accessor_struct(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens {
+    let accessor_fields = tracked_trait_fields(fields, array_fields).map(|(name, trait_tokens, is_array)| {
+        if is_array {
+            quote! { #name: #trait_tokens::TrackedArrayAccessor }
+        } else {
+            quote! { #name: #trait_tokens::TrackedAccessor }
+        }
+    });
+    quote! {
+        pub struct GeneratedTrackedAccessor<'a> {
+            #(pub #accessor_fields),*
+        }
+    }
+}
+
 fn impl_field(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens {
     let layout_fields = trait_fields(fields, array_fields).map(|(name, trait_tokens, _is_array)| {
         quote! { #name: #trait_tokens::make_layout(layout_info.get_field_layout(stringify!(#name)).ok_or(LayoutError)?)? }
     });
-    let accessor_fields = trait_fields_non_static(fields, array_fields).map(|(name, trait_tokens, _is_array)| {
-        quote! { #name: #trait_tokens::make_accessor(&layout.fields.#name, data) }
+    let accessor_fields = trait_fields_non_static(fields, array_fields).map(|(name, trait_tokens, is_array)| {
+        if is_array {
+            quote! { #name: #trait_tokens::make_accessor(&layout.fields.#name, data) }
+        } else {
+            quote! { #name: #trait_tokens::make_accessor_with_len(&layout.fields.#name, data, data_len) }
+        }
     });
     let field_spans = trait_fields(fields, array_fields).map(|(name, trait_tokens, _is_array)| {
         quote! { .chain(#trait_tokens::get_field_spans(&layout.fields.#name)) }
     });
+    let alignments = fields.iter().enumerate().map(|(i, field)| {
+        match field.ty {
+            Ty::Array(..) => {
+                let element = &array_fields.iter().find(|a| a.index == i).unwrap().element;
+                quote! { <#element as Field<'static>>::alignment() }
+            },
+            _ => {
+                let ty = field_type(field);
+                quote! { <#ty as Field<'static>>::alignment() }
+            }
+        }
+    });
+    let declared_ends = trait_fields(fields, array_fields).map(|(name, trait_tokens, _is_array)| {
+        quote! { #trait_tokens::declared_end(&outer.fields.#name) }
+    });
+    let named_field_spans = trait_fields(fields, array_fields).map(|(name, trait_tokens, _is_array)| {
+        quote! { .chain(#trait_tokens::get_named_field_spans(&layout.fields.#name, &dynamiclayout::join_field_path(path, stringify!(#name)))) }
+    });
+    // A runtime-sized array may only end the struct, and arrays of fields can't contain one
+    let field_count = fields.len();
+    let non_trailing_checks = trait_fields(fields, array_fields).take(field_count.saturating_sub(1))
+        .filter(|&(_, _, is_array)| !is_array)
+        .map(|(name, trait_tokens, _is_array)| {
+            quote! {
+                if #trait_tokens::runtime_array_offset(&layout_fields.#name).is_some() {
+                    return Err(LayoutError);
+                }
+            }
+        });
+    let runtime_array_offset = match trait_fields(fields, array_fields).last() {
+        Some((name, trait_tokens, false)) => quote! { #trait_tokens::runtime_array_offset(&layout.fields.#name) },
+        _ => quote! { None },
+    };
     quote!{
         impl<'a> Field<'a> for OriginalType {
             type Layout = GeneratedLayout;
@@ -184,20 +535,41 @@ fn impl_field(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens
                     let layout_fields = GeneratedLayoutFields {
                         #(#layout_fields),*
                     };
+                    #( #non_trailing_checks )*
+                    let declared_len = match layout_info.declared_size() {
+                        Some(size) if size > LengthType::max_value() as usize => return Err(LayoutError),
+                        size => size.map(|size| size as LengthType),
+                    };
                     let mut outer = GeneratedLayout {
                         len: 0,
+                        declared_len,
                         fields: layout_fields
                     };
                     let spans: Vec<_> = <OriginalType as Field>::get_field_spans(&outer).collect();
-                    outer.len = spans.iter().map(|span| span.offset + span.length).max().unwrap_or(0);
+                    // Nested structs count with their declared size, not only up to their last field
+                    let len = spans.iter().map(|span| span.offset as usize + span.length as usize)
+                        .chain(vec![ #( #declared_ends ),* ].into_iter().filter_map(|end: Option<usize>| end))
+                        .max().unwrap_or(0);
+                    if len > OffsetType::max_value() as usize {
+                        return Err(LayoutError);
+                    }
+                    outer.len = len as OffsetType;
                     dynamiclayout::validate_field_spans(spans)?;
-                    Ok(outer)
+                    // The first element of a trailing runtime-sized array must come after every other field
+                    match <OriginalType as Field>::runtime_array_offset(&outer) {
+                        Some(offset) if offset < outer.len => Err(LayoutError),
+                        _ => Ok(outer),
+                    }
                 } else {
                     Err(LayoutError)
                 }
             }
 
             unsafe fn make_accessor(layout: &Self::Layout, data: *mut u8) -> Self::Accessor {
+                <OriginalType as Field<'a>>::make_accessor_with_len(layout, data, 0)
+            }
+
+            unsafe fn make_accessor_with_len(layout: &Self::Layout, data: *mut u8, data_len: usize) -> Self::Accessor {
                 GeneratedAccessor {
                     #(#accessor_fields),*
                 }
@@ -209,6 +581,196 @@ fn impl_field(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens
                     #( #field_spans )*
                 )
             }
+
+            fn alignment() -> usize {
+                ::std::iter::empty() #( .chain(Some(#alignments)) )* .max().unwrap_or(1)
+            }
+
+            fn declared_len(layout: &Self::Layout) -> Option<LengthType> {
+                layout.declared_len
+            }
+
+            fn runtime_array_offset(layout: &Self::Layout) -> Option<OffsetType> {
+                #runtime_array_offset
+            }
+
+            fn get_named_field_spans(layout: &Self::Layout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+                Box::new(
+                    ::std::iter::empty()
+                    #( #named_field_spans )*
+                )
+            }
+        }
+    }
+}
+
+fn impl_tracked_field(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens {
+    let accessor_fields = tracked_trait_fields(fields, array_fields).map(|(name, trait_tokens, is_array)| {
+        if is_array {
+            quote! { #name: #trait_tokens::make_tracked_accessor(&layout.fields.#name, data, shift, tracker) }
+        } else {
+            quote! { #name: #trait_tokens::make_tracked_accessor_with_len(&layout.fields.#name, data, shift, data_len, tracker) }
+        }
+    });
+    quote!{
+        impl<'a> TrackedField<'a> for OriginalType {
+            type TrackedAccessor = GeneratedTrackedAccessor<'a>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::Layout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+                <OriginalType as TrackedField<'a>>::make_tracked_accessor_with_len(layout, data, shift, 0, tracker)
+            }
+
+            unsafe fn make_tracked_accessor_with_len(layout: &Self::Layout,
+                                                     data: *mut u8,
+                                                     shift: OffsetType,
+                                                     data_len: usize,
+                                                     tracker: &'a DirtyTracker) -> Self::TrackedAccessor {
+                GeneratedTrackedAccessor {
+                    #(#accessor_fields),*
+                }
+            }
+        }
+    }
+}
+
+fn impl_tracked_array_field() -> Tokens {
+    quote!{
+        impl<'a, L, A> TrackedArrayField<'a, L, A> for OriginalType
+            where L: ArrayHelper<'a, Item=<Self as Field<'a>>::Layout>,
+                A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> + 'a {
+
+            type TrackedArrayAccessor = Vec<GeneratedTrackedAccessor<'a>>;
+
+            unsafe fn make_tracked_accessor(layout: &Self::ArrayLayout,
+                                            data: *mut u8,
+                                            shift: OffsetType,
+                                            tracker: &'a DirtyTracker) -> Self::TrackedArrayAccessor {
+                dynamiclayout::make_tracked_array_accessor::<OriginalType, L>(layout, data, shift, tracker)
+            }
+        }
+    }
+}
+
+// Default-block uniforms have their own layouts with locations instead of offsets, and accessors that
+// write to a UniformSink. Arrays are one-dimensional, their elements are at consecutive locations.
+fn impl_uniform_field(fields: &Vec<Field>, array_fields: &Vec<ArrayFieldInfo>) -> Tokens {
+    let uniform_fields: Vec<_> = fields.iter().enumerate().map(|(i, field)| {
+        let name = field.ident.clone().unwrap();
+        match array_fields.iter().find(|a| a.index == i) {
+            Some(array_field) => {
+                if array_field.dimensions.len() > 1 {
+                    panic!("Arrays of arrays are not supported in default-block uniforms");
+                }
+                let element = &array_field.element;
+                let size = array_field.dimensions[0].size;
+                (quote! { #name: <#element as UniformArrayField<'static>>::UniformArrayLayout },
+                 quote! { #name: <#element as UniformArrayField<'a>>::UniformArrayAccessor },
+                 quote! { #name: <#element as UniformArrayField<'static>>::make_uniform_array_layout(layout_info.get_field_layout(stringify!(#name)).ok_or(LayoutError)?, #size)? },
+                 quote! { #name: <#element as UniformArrayField<'a>>::make_uniform_array_accessor(&layout.#name, sink) })
+            },
+            None => {
+                let ty = field_type(field);
+                (quote! { #name: <#ty as UniformField<'static>>::UniformLayout },
+                 quote! { #name: <#ty as UniformField<'a>>::UniformAccessor },
+                 quote! { #name: <#ty as UniformField<'static>>::make_uniform_layout(layout_info.get_field_layout(stringify!(#name)).ok_or(LayoutError)?)? },
+                 quote! { #name: <#ty as UniformField<'a>>::make_uniform_accessor(&layout.#name, sink) })
+            }
+        }
+    }).collect();
+    let layout_fields = uniform_fields.iter().map(|f| &f.0);
+    let accessor_fields = uniform_fields.iter().map(|f| &f.1);
+    let make_layout_fields = uniform_fields.iter().map(|f| &f.2);
+    let make_accessor_fields = uniform_fields.iter().map(|f| &f.3);
+    quote!{
+        pub struct GeneratedUniformLayout {
+            #(#layout_fields),*
+        }
+
+        impl GeneratedUniformLayout {
+            pub fn make_accessor<'a>(&self, sink: &'a UniformSink) -> GeneratedUniformAccessor<'a> {
+                <OriginalType as UniformField<'a>>::make_uniform_accessor(self, sink)
+            }
+        }
+
+        pub struct GeneratedUniformAccessor<'a> {
+            #(pub #accessor_fields),*
+        }
+
+        impl<'a> UniformField<'a> for OriginalType {
+            type UniformLayout = GeneratedUniformLayout;
+            type UniformAccessor = GeneratedUniformAccessor<'a>;
+
+            fn make_uniform_layout(layout_field: LayoutInfo) -> Result<Self::UniformLayout, LayoutError> {
+                if let LayoutInfo::StructField(layout_info) = layout_field {
+                    Ok(GeneratedUniformLayout {
+                        #(#make_layout_fields),*
+                    })
+                } else {
+                    Err(LayoutError)
+                }
+            }
+
+            fn make_uniform_accessor(layout: &Self::UniformLayout, sink: &'a UniformSink) -> Self::UniformAccessor {
+                GeneratedUniformAccessor {
+                    #(#make_accessor_fields),*
+                }
+            }
+        }
+
+        impl<'a> UniformArrayField<'a> for OriginalType {
+            type UniformArrayLayout = Vec<GeneratedUniformLayout>;
+            type UniformArrayAccessor = Vec<GeneratedUniformAccessor<'a>>;
+
+            fn make_uniform_array_layout(layout_field: LayoutInfo, len: usize) -> Result<Self::UniformArrayLayout, LayoutError> {
+                dynamiclayout::uniforms::make_uniform_struct_array_layout::<OriginalType>(layout_field, len)
+            }
+
+            fn make_uniform_array_accessor(layout: &Self::UniformArrayLayout, sink: &'a UniformSink) -> Self::UniformArrayAccessor {
+                layout.iter().map(|element| <OriginalType as UniformField<'a>>::make_uniform_accessor(element, sink)).collect()
+            }
+        }
+    }
+}
+
+// Arrays of this struct are accessed through a wrapper that derefs to a slice of element accessors,
+// and adds a column_<field> method for viewing one member of every element as a strided array.
+fn array_accessor(fields: &Vec<Field>) -> Tokens {
+    let columns = fields.iter().filter(|field| match field.ty {
+        Ty::Array(..) => false,
+        _ => true
+    }).map(|field| {
+        let name = field.ident.clone().unwrap();
+        let ty = field_type(field);
+        let column_name = Ident::new(format!("column_{}", name));
+        quote! {
+            pub fn #column_name(&mut self) -> Option<PrimitiveArrayAccessor<<<#ty as Field<'a>>::Accessor as ColumnAccessor>::Element>>
+                    where <#ty as Field<'a>>::Accessor: ColumnAccessor {
+                dynamiclayout::accessor::column_mut(&mut **self, |element| &mut element.#name)
+            }
+        }
+    });
+    quote!{
+        pub struct GeneratedArrayAccessor<'a, A: ArrayHelper<'a, Item=GeneratedAccessor<'a>>>(A::ArrayType);
+
+        impl<'a, A: ArrayHelper<'a, Item=GeneratedAccessor<'a>>> GeneratedArrayAccessor<'a, A> {
+            #(#columns)*
+        }
+
+        impl<'a, A: ArrayHelper<'a, Item=GeneratedAccessor<'a>>> ops::Deref for GeneratedArrayAccessor<'a, A> {
+            type Target = [GeneratedAccessor<'a>];
+
+            fn deref(&self) -> &[GeneratedAccessor<'a>] {
+                A::array_as_slice(&self.0)
+            }
+        }
+
+        impl<'a, A: ArrayHelper<'a, Item=GeneratedAccessor<'a>>> ops::DerefMut for GeneratedArrayAccessor<'a, A> {
+            fn deref_mut(&mut self) -> &mut [GeneratedAccessor<'a>] {
+                A::array_as_mut_slice(&mut self.0)
+            }
         }
     }
 }
@@ -220,19 +782,27 @@ fn impl_array_field() -> Tokens {
                 A: ArrayHelper<'a, Item=<Self as Field<'a>>::Accessor> + 'a {
 
             type ArrayLayout = L::ArrayType;
-            type ArrayAccessor = A::ArrayType;
+            type ArrayAccessor = GeneratedArrayAccessor<'a, A>;
 
             fn make_layout(layout_field: LayoutInfo) -> Result<Self::ArrayLayout, LayoutError> {
                 dynamiclayout::make_array_layout::<OriginalType, L>(layout_field)
             }
 
             unsafe fn make_accessor(layout: &Self::ArrayLayout, data: *mut u8) -> Self::ArrayAccessor {
-                dynamiclayout::make_array_accessor::<OriginalType, L, A>(layout, data)
+                GeneratedArrayAccessor(dynamiclayout::make_array_accessor::<OriginalType, L, A>(layout, data))
             }
 
             fn get_field_spans(layout: &Self::ArrayLayout) -> Box<Iterator<Item = FieldSpan>> {
                 dynamiclayout::get_array_field_spans::<OriginalType, L>(layout)
             }
+
+            fn declared_end(layout: &Self::ArrayLayout) -> Option<usize> {
+                dynamiclayout::get_array_declared_end::<OriginalType, L>(layout)
+            }
+
+            fn get_named_field_spans(layout: &Self::ArrayLayout, path: &str) -> Box<Iterator<Item = (String, FieldSpan)>> {
+                dynamiclayout::get_array_named_field_spans::<OriginalType, L>(layout, path)
+            }
         }
     }
 }
@@ -242,20 +812,39 @@ fn impl_dynamic_layout() -> Tokens {
         impl<'a> DynamicLayout<'a> for OriginalType {
             type Layout = GeneratedLayout;
             type Accessor = GeneratedAccessor<'a>;
+            type TrackedAccessor = GeneratedTrackedAccessor<'a>;
 
             fn load_layout(layout_info: &LoadStructLayout) -> Result<GeneratedLayout, LayoutError> {
                 <OriginalType as Field>::make_layout(LayoutInfo::StructField(layout_info))
             }
 
-            fn make_accessor(layout: &GeneratedLayout, data: &'a mut Data) -> Result<GeneratedAccessor<'a>, AccessorError> {
-                if data.len() < layout.required_data_len() {
-                    return Err(AccessorError {
-                        required_data_len: layout.required_data_len(),
-                        data_len: data.len(),
-                    });
+            fn required_data_len(layout: &GeneratedLayout) -> usize {
+                layout.required_data_len()
+            }
+
+            fn required_alignment(_layout: &GeneratedLayout) -> usize {
+                <OriginalType as Field>::alignment()
+            }
+
+            fn make_accessor(layout: &GeneratedLayout, mut data: Data<'a>) -> Result<GeneratedAccessor<'a>, AccessorError> {
+                dynamiclayout::check_data(&mut data, layout.required_data_len(), <OriginalType as Field>::alignment())?;
+                unsafe {
+                    let data_len = data.len();
+                    Ok(<OriginalType as Field>::make_accessor_with_len(layout, data.as_ptr(), data_len))
                 }
+            }
+
+            fn get_named_field_spans(layout: &GeneratedLayout) -> Box<Iterator<Item = (String, FieldSpan)>> {
+                <OriginalType as Field>::get_named_field_spans(layout, "")
+            }
+
+            fn make_tracked_accessor(layout: &GeneratedLayout,
+                                     mut data: Data<'a>,
+                                     tracker: &'a DirtyTracker) -> Result<GeneratedTrackedAccessor<'a>, AccessorError> {
+                dynamiclayout::check_data(&mut data, layout.required_data_len(), <OriginalType as Field>::alignment())?;
                 unsafe {
-                    Ok(<OriginalType as Field>::make_accessor(layout, data.as_ptr()))
+                    let data_len = data.len();
+                    Ok(<OriginalType as TrackedField>::make_tracked_accessor_with_len(layout, data.as_ptr(), 0, data_len, tracker))
                 }
             }
         }
@@ -265,13 +854,11 @@ fn impl_dynamic_layout() -> Tokens {
 fn trait_fields<'a>(fields: &'a Vec<Field>, array_fields: &'a Vec<ArrayFieldInfo>) -> Box<Iterator<Item = (Ident, Tokens, bool)> + 'a> {
     Box::new(fields.iter().enumerate().map(move |(i, field)| {
         let name = field.ident.clone().unwrap();
-        let ty = &field.ty;
-        match *ty {
-            Ty::Array(ref inner_ty, ref _len) => {
+        let ty = field_type(field);
+        match field.ty {
+            Ty::Array(..) => {
                 let array_field = array_fields.iter().find(|a| a.index == i).unwrap();
-                let layout_helper = &array_field.layout;
-                let accessor_helper = &array_field.accessor;
-                (name, quote! { <#inner_ty as ArrayField<'static, #layout_helper<'static>, #accessor_helper<'static>>> }, true)
+                (name, array_field.trait_tokens("ArrayField", quote! { 'static }), true)
             },
             _ => (name, quote! { <#ty as Field<'static>> }, false)
         }
@@ -281,15 +868,27 @@ fn trait_fields<'a>(fields: &'a Vec<Field>, array_fields: &'a Vec<ArrayFieldInfo
 fn trait_fields_non_static<'a>(fields: &'a Vec<Field>, array_fields: &'a Vec<ArrayFieldInfo>) -> Box<Iterator<Item = (Ident, Tokens, bool)> + 'a> {
     Box::new(fields.iter().enumerate().map(move |(i, field)| {
         let name = field.ident.clone().unwrap();
-        let ty = &field.ty;
-        match *ty {
-            Ty::Array(ref inner_ty, ref _len) => {
+        let ty = field_type(field);
+        match field.ty {
+            Ty::Array(..) => {
                 let array_field = array_fields.iter().find(|a| a.index == i).unwrap();
-                let layout_helper = &array_field.layout;
-                let accessor_helper = &array_field.accessor;
-                (name, quote! { <#inner_ty as ArrayField<'a, #layout_helper<'a>, #accessor_helper<'a>>> }, true)
+                (name, array_field.trait_tokens("ArrayField", quote! { 'a }), true)
             },
             _ => (name, quote! { <#ty as Field<'a>> }, false)
         }
     }))
 }
+
+fn tracked_trait_fields<'a>(fields: &'a Vec<Field>, array_fields: &'a Vec<ArrayFieldInfo>) -> Box<Iterator<Item = (Ident, Tokens, bool)> + 'a> {
+    Box::new(fields.iter().enumerate().map(move |(i, field)| {
+        let name = field.ident.clone().unwrap();
+        let ty = field_type(field);
+        match field.ty {
+            Ty::Array(..) => {
+                let array_field = array_fields.iter().find(|a| a.index == i).unwrap();
+                (name, array_field.trait_tokens("TrackedArrayField", quote! { 'a }), true)
+            },
+            _ => (name, quote! { <#ty as TrackedField<'a>> }, false)
+        }
+    }))
+}